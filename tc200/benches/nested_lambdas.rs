@@ -0,0 +1,39 @@
+/* Checks fdC("x0", numT, numT, appC(fdC("x1", ...), idC("x0"))) nested
+ * n deep, for growing n. Every binder extends the environment, so with a
+ * scoped environment the time per fdC should stay about the same.
+ *
+ * cargo bench
+ */
+use std::thread;
+use std::time::Instant;
+use tc200::*;
+
+fn nested_lambdas(depth: usize) -> AST {
+	let mut body = AST::AprimC("plusC", vec![AST::AidC("x0".to_string()), AST::AidC(format!("x{}", depth - 1))]);
+	for i in (0..depth).rev() {
+		let name = format!("x{}", i);
+		let fun = AST::AfdC(name.to_string(), Box::new(Type::NumT), Box::new(Type::NumT), Box::new(Type::BlankT), Box::new(body));
+		body = if i == 0 {
+			fun
+		} else {
+			AST::AappC(Box::new(fun), Box::new(AST::AidC(format!("x{}", i - 1))))
+		};
+	}
+	body
+}
+
+fn main() {
+	/* the checker recurses once per fdC */
+	let bench = thread::Builder::new().stack_size(1 << 30).spawn(|| {
+		println!("{:>8} {:>12} {:>14}", "depth", "time (ms)", "ns per fdC");
+		for depth in [2000, 4000, 8000, 16000, 32000].iter() {
+			let ast = nested_lambdas(*depth);
+			let start = Instant::now();
+			let result = Infer::new().infer(&ast, &mut Env::new());
+			let elapsed = start.elapsed();
+			assert_eq!(result, Type::FunT(Box::new(Type::NumT), Box::new(Type::NumT), Box::new(Type::TotalT)));
+			println!("{:>8} {:>12.2} {:>14}", depth, elapsed.as_secs_f64() * 1000.0, elapsed.as_nanos() / *depth as u128);
+		}
+	}).unwrap();
+	bench.join().unwrap();
+}
@@ -1,6 +1,9 @@
 use std::cmp::PartialEq;
 use std::clone::Clone;
 use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+use std::cell::RefCell;
 
 #[derive(Clone)]
 pub enum State {
@@ -8,26 +11,65 @@ pub enum State {
 	Sstr,
 	Snum,
 	Spunc,
+	Sliteral,
 }
 
 #[derive(Debug, Clone)]
 pub enum Token {
     Tnum(i32), /* number */
 	Tid(String), /* id */
+	Tstr(String), /* 'string literal' */
+	Thole(String), /* ?name */
     Ttype(Type),
 
 	// keywords
     TnumC,
-    TplusC,
-    TmultC,
+	TprimC(&'static str), /* an entry of PRIMITIVES */
 	TtrueC,
 	TfalseC,
-	TeqC,
 	TifC,
 	TidC,
 	TappC,
 	TfdC,
 	TrecC,
+	TletrecC,
+	TletC,
+	TtyLamC,
+	TtyAppC,
+	TtupleC,
+	TprojC,
+	TinlC,
+	TinrC,
+	TcaseC,
+	TemptyC,
+	TconsC,
+	TfirstC,
+	TrestC,
+	TisEmptyC,
+	TrecordC,
+	TgetC,
+	TdatatypeC,
+	TconC,
+	TmatchC,
+	TfoldC,
+	TunfoldC,
+	TboxC,
+	TunboxC,
+	TsetboxC,
+	TseqC,
+	TasC,
+	TholeC,
+
+	// type constructors
+	TfunT,
+	TforallT,
+	TtupleT,
+	TsumT,
+	TlistT,
+	TrecordT,
+	TnamedT,
+	TmuT,
+	TrefT,
 
 	// punctuations
 	TleftParen,
@@ -43,6 +85,7 @@ impl PartialEq for State {
         		(&State::Sstr, &State::Sstr) |
             		(&State::Snum, &State::Snum) |
         		(&State::Spunc, &State::Spunc) |
+        		(&State::Sliteral, &State::Sliteral) |
             		(&State::Sstart, &State::Sstart) => true,
         		_ => false,
         }
@@ -75,19 +118,48 @@ pub fn tokenize(input: String) -> Vec<Token> {
 
 	let mut buffer = String::new();
 	let mut token_list: Vec<Token> = Vec::new();
+	let mut in_literal = false;
 
-	let input: String = input.split_whitespace().collect();
+	let input: String = strip_whitespace(&input);
 	let input_vec: Vec<char> = input.chars().collect();
 
 	for i in 0 .. (input_vec.len() + 1) {
 		let c;
-		if i < input.len() {
+		if i < input_vec.len() {
 			c = input_vec[i];
 		} else {
 			c = '#';
 		}
+		/* everything up to the closing quote belongs to the string */
+		if in_literal {
+			if i == input_vec.len() {
+				panic!("Unterminated string literal: '{}", buffer)
+			} else if c == '\'' {
+				in_literal = false;
+			} else {
+				buffer.push(c);
+			}
+			continue;
+		}
 		match c {
-			'a' ..= 'z' | 'A' ..= 'Z' => {
+			'\'' => {
+				/*
+				Spunc -'-> Sliteral, and flush
+				S0 -'-> Sliteral
+				*/
+				if last_state != State::Sstart {
+					bind_token(&buffer, &mut token_list, &last_state);
+				}
+				buffer = String::new();
+				in_literal = true;
+				last_state = State::Sliteral;
+				continue;
+			},
+			'a' ..= 'z' | 'A' ..= 'Z' | '-' | '_' | '?' => {
+				/* '?' only starts a hole, it is not part of a name */
+				if c == '?' && (last_state == State::Sstr || last_state == State::Snum) {
+					panic!("Invalid Character: '?' can only start a hole, after {}", buffer)
+				}
 				state = State::Sstr;
 				/*
 				Sstr -a-> Sstr
@@ -151,6 +223,21 @@ pub fn tokenize(input: String) -> Vec<Token> {
 	token_list
 }
 
+/* drop the whitespace, except inside 'string literals' */
+pub fn strip_whitespace(input: &str) -> String {
+	let mut stripped = String::new();
+	let mut in_literal = false;
+	for c in input.chars() {
+		if c == '\'' {
+			in_literal = !in_literal;
+		}
+		if in_literal || !c.is_whitespace() {
+			stripped.push(c);
+		}
+	}
+	stripped
+}
+
 pub fn bind_token(input: &String, token_list: &mut Vec<Token>, state: &State){
 	let s = input.as_str();
 	match state {
@@ -168,24 +255,67 @@ pub fn bind_token(input: &String, token_list: &mut Vec<Token>, state: &State){
 			// println!("Snum, insert {}", input);
 			token_list.push(Token::Tnum(input.parse::<i32>().unwrap()));
 		}
+		State::Sliteral => token_list.push(Token::Tstr(input.to_string())),
 		State::Sstr => {
 			println!("Sstr, insert {}", input);
 			match s {
 				"numC" => token_list.push(Token::TnumC),
-				"plusC" => token_list.push(Token::TplusC),
-				"multC" => token_list.push(Token::TmultC),
 				"trueC" => token_list.push(Token::TtrueC),
 				"falseC" => token_list.push(Token::TfalseC),
-				"eqC" => token_list.push(Token::TeqC),
 				"ifC" => token_list.push(Token::TifC),
 				"idC" => token_list.push(Token::TidC),
 				"appC" => token_list.push(Token::TappC),
 				"fdC" => token_list.push(Token::TfdC),
 				"recC" => token_list.push(Token::TrecC),
+				"letrecC" => token_list.push(Token::TletrecC),
+				"letC" => token_list.push(Token::TletC),
+				"tyLamC" => token_list.push(Token::TtyLamC),
+				"tyAppC" => token_list.push(Token::TtyAppC),
+				"tupleC" => token_list.push(Token::TtupleC),
+				"projC" => token_list.push(Token::TprojC),
+				"inlC" => token_list.push(Token::TinlC),
+				"inrC" => token_list.push(Token::TinrC),
+				"caseC" => token_list.push(Token::TcaseC),
+				"emptyC" => token_list.push(Token::TemptyC),
+				"consC" => token_list.push(Token::TconsC),
+				"firstC" => token_list.push(Token::TfirstC),
+				"restC" => token_list.push(Token::TrestC),
+				"isEmptyC" => token_list.push(Token::TisEmptyC),
+				"recordC" => token_list.push(Token::TrecordC),
+				"getC" => token_list.push(Token::TgetC),
+				"datatypeC" => token_list.push(Token::TdatatypeC),
+				"conC" => token_list.push(Token::TconC),
+				"matchC" => token_list.push(Token::TmatchC),
+				"foldC" => token_list.push(Token::TfoldC),
+				"unfoldC" => token_list.push(Token::TunfoldC),
+				"boxC" => token_list.push(Token::TboxC),
+				"unboxC" => token_list.push(Token::TunboxC),
+				"setboxC" => token_list.push(Token::TsetboxC),
+				"seqC" => token_list.push(Token::TseqC),
+				"asC" => token_list.push(Token::TasC),
+				"holeC" => token_list.push(Token::TholeC),
 				"numT"=> token_list.push(Token::Ttype(Type::NumT)),
 				"boolT" => token_list.push(Token::Ttype(Type::BoolT)),
-				// "funT" => token_list.push(Token::Ttype(Type::FunT(Box::new(), Box::new()))),
-				_ => token_list.push(Token::Tid(s.to_string())),
+				"unitT" => token_list.push(Token::Ttype(Type::UnitT)),
+				"strT" => token_list.push(Token::Ttype(Type::StrT)),
+				"dynT" => token_list.push(Token::Ttype(Type::DynT)),
+				"totalT" => token_list.push(Token::Ttype(Type::TotalT)),
+				"divT" => token_list.push(Token::Ttype(Type::DivT)),
+				"_" => token_list.push(Token::Ttype(Type::BlankT)),
+				"funT" => token_list.push(Token::TfunT),
+				"forallT" => token_list.push(Token::TforallT),
+				"tupleT" => token_list.push(Token::TtupleT),
+				"sumT" => token_list.push(Token::TsumT),
+				"listT" => token_list.push(Token::TlistT),
+				"recordT" => token_list.push(Token::TrecordT),
+				"namedT" => token_list.push(Token::TnamedT),
+				"muT" => token_list.push(Token::TmuT),
+				"refT" => token_list.push(Token::TrefT),
+				_ if s.starts_with('?') => token_list.push(Token::Thole(s[1..].to_string())),
+				_ => match primitive(s) {
+					Some(prim) => token_list.push(Token::TprimC(prim.name)),
+					None => token_list.push(Token::Tid(s.to_string())),
+				},
 			}
 		}
 		_=> panic!("..."),
@@ -202,7 +332,24 @@ pub fn bind_token(input: &String, token_list: &mut Vec<Token>, state: &State){
 pub enum Type {
 	NumT,
 	BoolT,
-	FunT(Box<Type>, Box<Type>),
+	FunT(Box<Type>, Box<Type>, Box<Type>), /* param, result, and the effect of a call */
+	VarT(usize), /* type variable introduced by inference */
+	SchemeT(Vec<usize>, Box<Type>), /* generalized type, for all the listed variables */
+	BlankT, /* `_`, an annotation left for inference */
+	TvarT(String), /* type variable bound by tyLamC / forallT */
+	ForallT(String, Box<Type>),
+	TupleT(Vec<Type>),
+	SumT(Box<Type>, Box<Type>),
+	ListT(Box<Type>),
+	RecordT(Vec<(String, Type)>), /* fields sorted by name */
+	NamedT(String), /* declared by datatypeC, equal only to itself */
+	MuT(String, Box<Type>), /* iso-recursive type, see foldC / unfoldC */
+	UnitT,
+	RefT(Box<Type>), /* mutable box, created by boxC */
+	StrT,
+	DynT, /* checked at run time, consistent with every type */
+	TotalT, /* effect of a call that always returns */
+	DivT, /* effect of a call that may diverge, see recC */
 }
 
 impl PartialEq for Type {
@@ -211,13 +358,227 @@ impl PartialEq for Type {
         let second = other.clone();
 		match (first, second) {
 			(Type::NumT, Type::NumT) |
-			(Type::BoolT, Type::BoolT) => true,
-			(Type::FunT(_a, _b), Type::FunT(_x, _y)) => true,
+			(Type::BoolT, Type::BoolT) |
+			(Type::BlankT, Type::BlankT) |
+			(Type::UnitT, Type::UnitT) |
+			(Type::StrT, Type::StrT) |
+			(Type::DynT, Type::DynT) |
+			(Type::TotalT, Type::TotalT) |
+			(Type::DivT, Type::DivT) => true,
+			(Type::FunT(a, b, e), Type::FunT(x, y, z)) => a == x && b == y && e == z,
+			(Type::VarT(a), Type::VarT(x)) => a == x,
+			(Type::SchemeT(a, b), Type::SchemeT(x, y)) => a == x && b == y,
+			(Type::TvarT(a), Type::TvarT(x)) => a == x,
+			(Type::TupleT(a), Type::TupleT(x)) => a == x,
+			(Type::SumT(a, b), Type::SumT(x, y)) => a == x && b == y,
+			(Type::ListT(a), Type::ListT(x)) => a == x,
+			(Type::RecordT(a), Type::RecordT(x)) => a == x,
+			(Type::NamedT(a), Type::NamedT(x)) => a == x,
+			(Type::RefT(a), Type::RefT(x)) => a == x,
+			/* equal up to the name of the bound variable */
+			(Type::ForallT(a, b), Type::ForallT(x, y)) | (Type::MuT(a, b), Type::MuT(x, y)) => {
+				if a == x {
+					b == y
+				} else {
+					let mut avoid = free_tyvars(&b);
+					avoid.extend(free_tyvars(&y));
+					let fresh = Type::TvarT(fresh_tyvar(&a, &avoid));
+					type_subst(&b, &a, &fresh) == type_subst(&y, &x, &fresh)
+				}
+			},
 			_ => false,
 		}
 	}
 }
 
+/* 0 -> 'a, 1 -> 'b, ..., 26 -> 'a1, ... */
+pub fn var_name(v: usize) -> String {
+	let letter = ((b'a' + (v % 26) as u8) as char).to_string();
+	if v < 26 {
+		format!("'{}", letter)
+	} else {
+		format!("'{}{}", letter, v / 26)
+	}
+}
+
+impl fmt::Display for Type {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Type::NumT => write!(f, "num"),
+			Type::BoolT => write!(f, "bool"),
+			Type::FunT(a, b, effect) => {
+				/* a call that may diverge is written with ~> */
+				let arrow = if **effect == Type::DivT { "~>" } else { "->" };
+				match **a {
+					Type::FunT(_, _, _) | Type::SchemeT(_, _) | Type::ForallT(_, _) | Type::MuT(_, _) => write!(f, "({}) {} {}", a, arrow, b),
+					_ => write!(f, "{} {} {}", a, arrow, b),
+				}
+			},
+			Type::VarT(v) => write!(f, "{}", var_name(*v)),
+			Type::SchemeT(vars, body) => {
+				write!(f, "forall")?;
+				let effects = effect_vars(body);
+				for v in vars.iter().filter(|v| !effects.contains(v)) {
+					write!(f, " {}", var_name(*v))?;
+				}
+				write!(f, ". {}", body)
+			},
+			Type::BlankT => write!(f, "_"),
+			Type::TvarT(a) => write!(f, "{}", a),
+			Type::ForallT(a, body) => write!(f, "forall {}. {}", a, body),
+			Type::TupleT(items) => {
+				write!(f, "(")?;
+				for (index, item) in items.iter().enumerate() {
+					if index > 0 {
+						write!(f, " * ")?;
+					}
+					match item {
+						Type::FunT(_, _, _) | Type::SchemeT(_, _) | Type::ForallT(_, _) | Type::MuT(_, _) => write!(f, "({})", item)?,
+						_ => write!(f, "{}", item)?,
+					}
+				}
+				write!(f, ")")
+			},
+			Type::SumT(a, b) => {
+				write!(f, "(")?;
+				for (index, item) in [a, b].iter().enumerate() {
+					if index > 0 {
+						write!(f, " + ")?;
+					}
+					match ***item {
+						Type::FunT(_, _, _) | Type::SchemeT(_, _) | Type::ForallT(_, _) | Type::MuT(_, _) => write!(f, "({})", item)?,
+						_ => write!(f, "{}", item)?,
+					}
+				}
+				write!(f, ")")
+			},
+			Type::ListT(a) => match **a {
+				Type::FunT(_, _, _) | Type::SchemeT(_, _) | Type::ForallT(_, _) | Type::MuT(_, _) => write!(f, "({}) list", a),
+				_ => write!(f, "{} list", a),
+			},
+			Type::RefT(a) => match **a {
+				Type::FunT(_, _, _) | Type::SchemeT(_, _) | Type::ForallT(_, _) | Type::MuT(_, _) => write!(f, "({}) ref", a),
+				_ => write!(f, "{} ref", a),
+			},
+			Type::UnitT => write!(f, "unit"),
+			Type::StrT => write!(f, "str"),
+			Type::DynT => write!(f, "dyn"),
+			Type::TotalT => write!(f, "total"),
+			Type::DivT => write!(f, "div"),
+			Type::RecordT(fields) => {
+				write!(f, "{{")?;
+				for (index, (name, t)) in fields.iter().enumerate() {
+					if index > 0 {
+						write!(f, ", ")?;
+					}
+					write!(f, "{}: {}", name, t)?;
+				}
+				write!(f, "}}")
+			},
+			Type::NamedT(name) => write!(f, "{}", name),
+			Type::MuT(a, body) => write!(f, "mu {}. {}", a, body),
+		}
+	}
+}
+
+impl Type {
+	/* the types directly inside this one */
+	pub fn children(&self) -> Vec<&Type> {
+		match self {
+			Type::FunT(a, b, effect) => vec![a, b, effect],
+			Type::SumT(a, b) => vec![a, b],
+			Type::SchemeT(_, body) | Type::ForallT(_, body) | Type::MuT(_, body) | Type::ListT(body) | Type::RefT(body) => vec![body],
+			Type::TupleT(items) => items.iter().collect(),
+			Type::RecordT(fields) => fields.iter().map(|(_name, t)| t).collect(),
+			_ => Vec::new(),
+		}
+	}
+
+	/* rebuild the type with `f` applied to each child */
+	pub fn map_children(&self, f: &mut dyn FnMut(&Type) -> Type) -> Type {
+		match self {
+			Type::FunT(a, b, effect) => Type::FunT(Box::new(f(a)), Box::new(f(b)), Box::new(f(effect))),
+			Type::SumT(a, b) => Type::SumT(Box::new(f(a)), Box::new(f(b))),
+			Type::ListT(a) => Type::ListT(Box::new(f(a))),
+			Type::RefT(a) => Type::RefT(Box::new(f(a))),
+			Type::RecordT(fields) => Type::RecordT(fields.iter().map(|(name, t)| (name.to_string(), f(t))).collect()),
+			Type::SchemeT(vars, body) => Type::SchemeT(vars.clone(), Box::new(f(body))),
+			Type::ForallT(a, body) => Type::ForallT(a.to_string(), Box::new(f(body))),
+			Type::MuT(a, body) => Type::MuT(a.to_string(), Box::new(f(body))),
+			Type::TupleT(items) => Type::TupleT(items.iter().map(f).collect()),
+			_ => self.clone(),
+		}
+	}
+
+	/* the same binder (forallT or muT) with another variable and body */
+	pub fn rebind(&self, a: String, body: Type) -> Type {
+		match self {
+			Type::MuT(_, _) => Type::MuT(a, Box::new(body)),
+			_ => Type::ForallT(a, Box::new(body)),
+		}
+	}
+
+	/* same constructor, same arity and same names, ignoring the children */
+	pub fn same_shape(&self, other: &Type) -> bool {
+		self.map_children(&mut |_| Type::BlankT) == other.map_children(&mut |_| Type::BlankT)
+	}
+}
+
+/* --------------- Primitive Table ---------------
+ * Every primitive operation is one row: its keyword, the types of its
+ * params (the arity is their number) and its result type. VarT(0) stands
+ * for any type, shared between the params. The tokenizer, parser, type
+ * checker and evaluator all go through this table, so a new primitive only
+ * needs a row.
+ */
+pub struct Primitive {
+	pub name: &'static str,
+	pub params: &'static [Type],
+	pub ret: Type,
+	pub error: &'static str, /* panic message when an operand does not fit */
+	pub equality: bool, /* its type variables only stand for equality types */
+	pub run: for<'a> fn(&[Value<'a>]) -> Value<'a>, /* what the evaluator does with the operands */
+}
+
+pub static PRIMITIVES: &[Primitive] = &[
+	Primitive { name: "plusC", params: &[Type::NumT, Type::NumT], ret: Type::NumT, error: "Invalid in plus!", equality: false, run: |args| Value::Num(num(&args[0]) + num(&args[1])) },
+	Primitive { name: "minusC", params: &[Type::NumT, Type::NumT], ret: Type::NumT, error: "Invalid in minus!", equality: false, run: |args| Value::Num(num(&args[0]) - num(&args[1])) },
+	Primitive { name: "multC", params: &[Type::NumT, Type::NumT], ret: Type::NumT, error: "Invalid in mult!", equality: false, run: |args| Value::Num(num(&args[0]) * num(&args[1])) },
+	Primitive { name: "divC", params: &[Type::NumT, Type::NumT], ret: Type::NumT, error: "Invalid in div!", equality: false, run: |args| match num(&args[1]) { 0 => panic!("Division by zero!"), d => Value::Num(num(&args[0]) / d) } },
+	Primitive { name: "modC", params: &[Type::NumT, Type::NumT], ret: Type::NumT, error: "Invalid in mod!", equality: false, run: |args| match num(&args[1]) { 0 => panic!("Division by zero!"), d => Value::Num(num(&args[0]) % d) } },
+	Primitive { name: "ltC", params: &[Type::NumT, Type::NumT], ret: Type::BoolT, error: "Invalid in lt!", equality: false, run: |args| Value::Bool(num(&args[0]) < num(&args[1])) },
+	Primitive { name: "leC", params: &[Type::NumT, Type::NumT], ret: Type::BoolT, error: "Invalid in le!", equality: false, run: |args| Value::Bool(num(&args[0]) <= num(&args[1])) },
+	Primitive { name: "eqC", params: &[Type::VarT(0), Type::VarT(0)], ret: Type::BoolT, error: "Eq not matched!", equality: true, run: |args| Value::Bool(values_equal(&args[0], &args[1])) },
+	Primitive { name: "andC", params: &[Type::BoolT, Type::BoolT], ret: Type::BoolT, error: "Invalid in and!", equality: false, run: |args| Value::Bool(boolean(&args[0]) && boolean(&args[1])) },
+	Primitive { name: "orC", params: &[Type::BoolT, Type::BoolT], ret: Type::BoolT, error: "Invalid in or!", equality: false, run: |args| Value::Bool(boolean(&args[0]) || boolean(&args[1])) },
+	Primitive { name: "notC", params: &[Type::BoolT], ret: Type::BoolT, error: "Invalid in not!", equality: false, run: |args| Value::Bool(!boolean(&args[0])) },
+	Primitive { name: "strAppendC", params: &[Type::StrT, Type::StrT], ret: Type::StrT, error: "Invalid in strAppend!", equality: false, run: |args| Value::Str(string(&args[0]) + &string(&args[1])) },
+	Primitive { name: "strLenC", params: &[Type::StrT], ret: Type::NumT, error: "Invalid in strLen!", equality: false, run: |args| Value::Num(string(&args[0]).len() as i32) },
+	Primitive { name: "strEqC", params: &[Type::StrT, Type::StrT], ret: Type::BoolT, error: "Invalid in strEq!", equality: false, run: |args| Value::Bool(string(&args[0]) == string(&args[1])) },
+];
+
+pub fn primitive(name: &str) -> Option<&'static Primitive> {
+	PRIMITIVES.iter().find(|prim| prim.name == name)
+}
+
+impl Primitive {
+	pub fn arity(&self) -> usize {
+		self.params.len()
+	}
+
+	/* the curried signature, e.g. eqC : forall 'a. 'a -> 'a -> bool */
+	pub fn signature(&self) -> Type {
+		let fun_type = self.params.iter().rev().fold(self.ret.clone(), |acc, param| Type::FunT(Box::new(param.clone()), Box::new(acc), Box::new(Type::TotalT)));
+		let mut vars = free_vars(&fun_type);
+		vars.dedup();
+		if vars.is_empty() {
+			fun_type
+		} else {
+			Type::SchemeT(vars, Box::new(fun_type))
+		}
+	}
+}
+
 pub fn is_type(check_type: &Token) -> bool {
     match check_type {
         Token::Ttype(_t) => true,
@@ -225,22 +586,228 @@ pub fn is_type(check_type: &Token) -> bool {
     }
 }
 
+/* a type written with one or more tokens:
+ * numT, boolT, unitT, strT, _, a (type variable), funT(T, T), funT(T, T, totalT),
+ * forallT("a", T),
+ * tupleT(T, ..., T), sumT(T, T), listT(T), recordT(("x", T), ...),
+ * namedT("Name"), muT("t", T), refT(T)
+ */
+pub fn is_type_expr(tk_list: &[Token]) -> bool {
+	if tk_list.is_empty() {
+		return false;
+	}
+	match &tk_list[0] {
+		Token::Ttype(_) | Token::Tid(_) => tk_list.len() == 1,
+		Token::TnamedT => tk_list.len() == 6 && tk_list[1] == Token::TleftParen && is_name(&tk_list[2..5]) && tk_list[5] == Token::TrightParen,
+		Token::TrecordT => {
+			if tk_list.len() < 3 || tk_list[1] != Token::TleftParen || tk_list[tk_list.len() - 1] != Token::TrightParen {
+				return false;
+			}
+			tk_list.len() == 3 || split_args(&tk_list[2..(tk_list.len() - 1)]).iter().all(|field| is_field(field, is_type_expr))
+		},
+		Token::TfunT | Token::TforallT | Token::TmuT | Token::TtupleT | Token::TsumT | Token::TlistT | Token::TrefT => {
+			if tk_list.len() < 4 || tk_list[1] != Token::TleftParen || tk_list[tk_list.len() - 1] != Token::TrightParen {
+				return false;
+			}
+			let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+			match tk_list[0] {
+				Token::TfunT => (args.len() == 2 || args.len() == 3) && args.iter().all(|arg| is_type_expr(arg)),
+				Token::TsumT => args.len() == 2 && is_type_expr(args[0]) && is_type_expr(args[1]),
+				Token::TtupleT => args.iter().all(|arg| is_type_expr(arg)),
+				Token::TlistT | Token::TrefT => args.len() == 1 && is_type_expr(args[0]),
+				_ => args.len() == 2 && is_name(args[0]) && is_type_expr(args[1]),
+			}
+		},
+		_ => false,
+	}
+}
+
+/* ("x", X), where X is checked by `is_value` */
+pub fn is_field(tk_list: &[Token], is_value: fn(&[Token]) -> bool) -> bool {
+	if tk_list.len() < 7 || tk_list[0] != Token::TleftParen || tk_list[tk_list.len() - 1] != Token::TrightParen {
+		return false;
+	}
+	let args = split_args(&tk_list[1..(tk_list.len() - 1)]);
+	args.len() == 2 && is_name(args[0]) && !args[1].is_empty() && is_value(args[1])
+}
+
+pub fn split_field(tk_list: &[Token]) -> (String, &[Token]) {
+	let args = split_args(&tk_list[1..(tk_list.len() - 1)]);
+	(get_name(&args[0][1]), args[1])
+}
+
+/* sort the fields so that records are equal whatever the order they are written in */
+pub fn record_type(mut fields: Vec<(String, Type)>) -> Type {
+	fields.sort_by(|a, b| a.0.cmp(&b.0));
+	for pair in fields.windows(2) {
+		if pair[0].0 == pair[1].0 {
+			panic!("Duplicate field in record: {}", pair[0].0)
+		}
+	}
+	Type::RecordT(fields)
+}
+
+pub fn parse_type(tk_list: &[Token]) -> Type {
+	if !is_type_expr(tk_list) {
+		panic!("Not a valid type")
+	}
+	match &tk_list[0] {
+		Token::Ttype(t) => t.clone(),
+		Token::Tid(a) => Type::TvarT(a.to_string()),
+		Token::TfunT => {
+			/* the effect is inferred unless it is given */
+			let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+			let effect = if args.len() == 3 { parse_type(args[2]) } else { Type::BlankT };
+			Type::FunT(Box::new(parse_type(args[0])), Box::new(parse_type(args[1])), Box::new(effect))
+		},
+		Token::TtupleT => {
+			let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+			Type::TupleT(args.into_iter().map(parse_type).collect())
+		},
+		Token::TsumT => {
+			let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+			Type::SumT(Box::new(parse_type(args[0])), Box::new(parse_type(args[1])))
+		},
+		Token::TlistT => {
+			let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+			Type::ListT(Box::new(parse_type(args[0])))
+		},
+		Token::TrefT => {
+			let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+			Type::RefT(Box::new(parse_type(args[0])))
+		},
+		Token::TnamedT => Type::NamedT(get_name(&tk_list[3])),
+		Token::TrecordT => {
+			let mut fields = Vec::new();
+			if tk_list.len() > 3 {
+				for field in split_args(&tk_list[2..(tk_list.len() - 1)]) {
+					let (name, t) = split_field(field);
+					fields.push((name, parse_type(t)));
+				}
+			}
+			record_type(fields)
+		},
+		Token::TmuT => {
+			let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+			Type::MuT(get_name(&args[0][1]), Box::new(parse_type(args[1])))
+		},
+		_ => {
+			let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+			Type::ForallT(get_name(&args[0][1]), Box::new(parse_type(args[1])))
+		},
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum AST {
 	AnumC(i32),
     AidC(String),
-	AplusC(Box<AST>, Box<AST>),
-	AmultC(Box<AST>, Box<AST>),
+	AprimC(&'static str, Vec<AST>),
 	AtrueC(bool),
 	AfalseC(bool),
 	AifC(Box<AST>, Box<AST>, Box<AST>),
 	AappC(Box<AST>, Box<AST>),
-	AfdC(String, Box<Type>, Box<Type>, Box<AST>),
-	AeqC(Box<AST>, Box<AST>),
+	AfdC(String, Box<Type>, Box<Type>, Box<Type>, Box<AST>), /* the effect is _ unless declared */
 	ArecC(String, String, Box<Type>, Box<Type>, Box<AST>, Box<AST>),
+	AletrecC(Vec<(String, String, Type, Type, AST)>, Box<AST>),
+	AletC(String, Box<AST>, Box<AST>),
+	AtyLamC(String, Box<AST>),
+	AtyAppC(Box<AST>, Type),
+	AtupleC(Vec<AST>),
+	AprojC(Box<AST>, usize),
+	AinlC(Box<AST>, Type),
+	AinrC(Box<AST>, Type),
+	AcaseC(Box<AST>, String, Box<AST>, String, Box<AST>),
+	AemptyC(Type),
+	AconsC(Box<AST>, Box<AST>),
+	AfirstC(Box<AST>),
+	ArestC(Box<AST>),
+	AisEmptyC(Box<AST>),
+	ArecordC(Vec<(String, AST)>),
+	AgetC(Box<AST>, String),
+	AdatatypeC(String, Vec<(String, Vec<Type>)>, Box<AST>),
+	AconC(String, Vec<AST>),
+	AmatchC(Box<AST>, Vec<(Option<String>, Vec<String>, AST)>), /* None is the `_` pattern */
+	AfoldC(Type, Box<AST>),
+	AunfoldC(Box<AST>),
+	AboxC(Box<AST>),
+	AunboxC(Box<AST>),
+	AsetboxC(Box<AST>, Box<AST>),
+	AseqC(Vec<AST>),
+	AasC(Box<AST>, Type),
+	AholeC(String),
+	AstrC(String),
+	AcastC(Box<AST>, Type, Type, String, String), /* inserted by the checker: from, to, and the labels blamed for the value and for its context */
 	None /* for Token::Tcomma and Token::Tquote */ ,
     AtypeC(Type),
 }
 
+impl AST {
+	/* the sub-expressions, each with the names bound around it by this node */
+	pub fn scoped_children(&self) -> Vec<(Vec<&String>, &AST)> {
+		match self {
+			AST::AfdC(name, _, _, _, body) => vec![(vec![name], body)],
+			AST::ArecC(name, param, _, _, body1, body2) => vec![(vec![name, param], body1), (vec![name], body2)],
+			AST::AletrecC(bindings, body) => {
+				let names: Vec<&String> = bindings.iter().map(|(name, _, _, _, _)| name).collect();
+				let mut children: Vec<(Vec<&String>, &AST)> = Vec::new();
+				for (_name, param, _, _, fun_body) in bindings.iter() {
+					let mut bound = names.clone();
+					bound.push(param);
+					children.push((bound, fun_body));
+				}
+				children.push((names, body));
+				children
+			},
+			AST::AletC(name, rhs, body) => vec![(vec![], rhs), (vec![name], body)],
+			AST::AcaseC(expr, left, left_body, right, right_body) => vec![(vec![], expr), (vec![left], left_body), (vec![right], right_body)],
+			AST::AmatchC(expr, arms) => {
+				let mut children = vec![(vec![], &**expr)];
+				for (_variant, vars, body) in arms.iter() {
+					children.push((vars.iter().filter(|var| *var != "_").collect(), body));
+				}
+				children
+			},
+			AST::AprimC(_, exprs) | AST::AtupleC(exprs) | AST::AconC(_, exprs) | AST::AseqC(exprs) => exprs.iter().map(|expr| (vec![], expr)).collect(),
+			AST::ArecordC(fields) => fields.iter().map(|(_name, expr)| (vec![], expr)).collect(),
+			AST::AifC(a, b, c) => vec![(vec![], a), (vec![], b), (vec![], c)],
+			AST::AappC(a, b) | AST::AconsC(a, b) | AST::AsetboxC(a, b) => vec![(vec![], a), (vec![], b)],
+			AST::AtyLamC(_, expr) | AST::AtyAppC(expr, _) | AST::AprojC(expr, _) | AST::AinlC(expr, _) | AST::AinrC(expr, _) |
+			AST::AfirstC(expr) | AST::ArestC(expr) | AST::AisEmptyC(expr) | AST::AgetC(expr, _) | AST::AdatatypeC(_, _, expr) |
+			AST::AfoldC(_, expr) | AST::AunfoldC(expr) | AST::AboxC(expr) | AST::AunboxC(expr) | AST::AasC(expr, _) |
+			AST::AcastC(expr, _, _, _, _) => vec![(vec![], expr)],
+			AST::AnumC(_) | AST::AidC(_) | AST::AtrueC(_) | AST::AfalseC(_) | AST::AemptyC(_) | AST::AholeC(_) | AST::AstrC(_) |
+			AST::None | AST::AtypeC(_) => vec![],
+		}
+	}
+
+	/* the sub-expressions, for rewriting them in place */
+	pub fn children_mut(&mut self) -> Vec<&mut AST> {
+		match self {
+			AST::AletrecC(bindings, body) => {
+				let mut children: Vec<&mut AST> = bindings.iter_mut().map(|(_, _, _, _, fun_body)| fun_body).collect();
+				children.push(body);
+				children
+			},
+			AST::AmatchC(expr, arms) => {
+				let mut children: Vec<&mut AST> = vec![expr];
+				children.extend(arms.iter_mut().map(|(_variant, _vars, body)| body));
+				children
+			},
+			AST::AprimC(_, exprs) | AST::AtupleC(exprs) | AST::AconC(_, exprs) | AST::AseqC(exprs) => exprs.iter_mut().collect(),
+			AST::ArecordC(fields) => fields.iter_mut().map(|(_name, expr)| expr).collect(),
+			AST::AifC(a, b, c) | AST::AcaseC(a, _, b, _, c) => vec![a, b, c],
+			AST::ArecC(_, _, _, _, a, b) | AST::AletC(_, a, b) | AST::AappC(a, b) | AST::AconsC(a, b) | AST::AsetboxC(a, b) => vec![a, b],
+			AST::AfdC(_, _, _, _, expr) | AST::AtyLamC(_, expr) | AST::AtyAppC(expr, _) | AST::AprojC(expr, _) | AST::AinlC(expr, _) |
+			AST::AinrC(expr, _) | AST::AfirstC(expr) | AST::ArestC(expr) | AST::AisEmptyC(expr) | AST::AgetC(expr, _) |
+			AST::AdatatypeC(_, _, expr) | AST::AfoldC(_, expr) | AST::AunfoldC(expr) | AST::AboxC(expr) | AST::AunboxC(expr) |
+			AST::AasC(expr, _) | AST::AcastC(expr, _, _, _, _) => vec![expr],
+			AST::AnumC(_) | AST::AidC(_) | AST::AtrueC(_) | AST::AfalseC(_) | AST::AemptyC(_) | AST::AholeC(_) | AST::AstrC(_) |
+			AST::None | AST::AtypeC(_) => vec![],
+		}
+	}
+}
+
 /* --------------- Parser Function ---------------
  * @parameter: a vector of tokens
  * @return: an AST
@@ -253,7 +820,7 @@ pub fn parse(tk_list: &[Token]) -> AST {
     /* punctuations */
 	(tk_list[0] == Token::TleftParen || tk_list[0] == Token::TrightParen || tk_list[0] == Token::Tcomma || tk_list[0] == Token::Tquote) ||
     /* keywords */
-    (tk_list[0] == Token::TnumC || tk_list[0] == Token::TplusC || tk_list[0] == Token::TmultC || tk_list[0] == Token::TeqC || tk_list[0] == Token::TifC || tk_list[0] == Token::TidC || tk_list[0] == Token::TappC || tk_list[0] == Token::TrecC) {
+    (tk_list[0] == Token::TnumC || tk_list[0] == Token::TifC || tk_list[0] == Token::TidC || tk_list[0] == Token::TappC || tk_list[0] == Token::TrecC) {
 		panic!("Invalid Token (length = 1).")
 	}
 
@@ -273,6 +840,18 @@ pub fn parse(tk_list: &[Token]) -> AST {
             AST::AidC(s)
 		},
         Token::Ttype(t) => AST::AtypeC(t),
+		Token::Tstr(s) => {
+			if tk_list.len() != 1 {
+				panic!("Invalid Character after String.")
+			}
+			AST::AstrC(s)
+		},
+		Token::Thole(name) => {
+			if tk_list.len() != 1 {
+				panic!("Invalid Character after Hole.")
+			}
+			AST::AholeC(name)
+		},
 
 		Token::TtrueC => {
 			if tk_list.len() != 1 {
@@ -347,59 +926,10 @@ pub fn parse(tk_list: &[Token]) -> AST {
         	}
 		} /* [END] Token::TidC */ ,
 
-		Token::TplusC => {
-            if tk_list.len() < 6 {
-                panic!("Incorrect format of plusC")
-            }
-			if tk_list[1] != Token::TleftParen {
-				panic!("Missing Left Parenthesis.")
-			}
-			if tk_list[tk_list.len() - 1] != Token::TrightParen {
-				panic!("Missing Right Parentesis.")
-			}
-
-            /* plusC(x, y) */
-			if tk_list.len() == 6 && tk_list[3] == Token::Tcomma {
-                let temp = tk_list[2].clone();
-				let first: &[Token] = &vec![temp];
-                let temp = tk_list[4].clone();
-				let second: &[Token] = &vec![temp];
-				AST::AplusC(Box::new(parse(first)), Box::new(parse(second)))
+		Token::TprimC(name) => {
+			if tk_list.len() < 4 {
+				panic!("Incorrect format of {}", name)
 			}
-
-            /* plusC(numC(x), numC(y)) */
-            else if tk_list.len() == 12 && tk_list[6] == Token::Tcomma {
-				let first: &[Token] = &tk_list[2..6];
-				let second: &[Token] = &tk_list[7..(tk_list.len() - 1)];
-				AST::AplusC(Box::new(parse(first)), Box::new(parse(second)))
-			} else /* there are things in first and second param */ {
-                let start_position = 2;
-                let mut end_position = 2;
-                for (index, token) in tk_list.iter().enumerate() {
-                    if *token == Token::Tcomma {
-                        if is_key_word(&tk_list[start_position..index]) {
-                            end_position = index;
-                            break;
-                        }
-                    }
-                }
-
-                if tk_list.len() < (end_position + 3) {
-                    panic!("Invalid format of plusC.")
-                } else if start_position == end_position || (end_position + 1) == (tk_list.len() - 1) {
-                    panic!("Parsing Error Occurred!")
-                } else {
-                    let first: &[Token] = &tk_list[start_position..end_position];
-                    let second: &[Token] = &tk_list[(end_position + 1) .. tk_list.len() - 1];
-                    AST::AplusC(Box::new(parse(first)), Box::new(parse(second)))
-                }
-            }
-		} /* [END] Token::TplusC */ ,
-
-        Token::TmultC => {
-            if tk_list.len() < 6 {
-                panic!("Incorrect format of multC")
-            }
 			if tk_list[1] != Token::TleftParen {
 				panic!("Missing Left Parenthesis.")
 			}
@@ -407,87 +937,17 @@ pub fn parse(tk_list: &[Token]) -> AST {
 				panic!("Missing Right Parentesis.")
 			}
 
-            /* multC(x, y) */
-			if tk_list.len() == 6 && tk_list[3] == Token::Tcomma {
-                let temp = tk_list[2].clone();
-				let first: &[Token] = &vec![temp];
-                let temp = tk_list[4].clone();
-				let second: &[Token] = &vec![temp];
-				AST::AmultC(Box::new(parse(first)), Box::new(parse(second)))
-			}
-
-            /* multC(numC(x), numC(y)) */
-            else if tk_list.len() == 12 && tk_list[6] == Token::Tcomma {
-				let first: &[Token] = &tk_list[2..6];
-				let second: &[Token] = &tk_list[7..(tk_list.len() - 1)];
-				AST::AmultC(Box::new(parse(first)), Box::new(parse(second)))
-			} else {
-                let start_position = 2;
-                let mut end_position = 2;
-                for (index, token) in tk_list.iter().enumerate() {
-                    if *token == Token::Tcomma {
-                        if is_key_word(&tk_list[start_position..index]) {
-                            end_position = index;
-                            break;
-                        }
-                    }
-                }
-                if tk_list.len() < (end_position + 3) {
-                    panic!("Invalid format of multC.")
-                } else if start_position == end_position || (end_position + 1) == (tk_list.len() - 1) {
-                    panic!("Parsing Error Occurred!")
-                } else {
-                    let first: &[Token] = &tk_list[start_position..end_position];
-                    let second: &[Token] = &tk_list[(end_position + 1) .. tk_list.len() - 1];
-                    AST::AmultC(Box::new(parse(first)), Box::new(parse(second)))
-                }
-            }
-        } /* [END] Token::TmultC */ ,
-
-        Token::TeqC => {
-            if tk_list.len() < 6 {
-                panic!("Incorrect format of eqC")
-            }
-			if tk_list[1] != Token::TleftParen {
-				panic!("Missing Left Parenthesis.")
+			/* plusC(x, y), notC(x), ... with as many params as the table says */
+			let prim = primitive(name).unwrap();
+			let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+			if args.len() != prim.arity() {
+				panic!("{} takes {} params, found {}.", name, prim.arity(), args.len())
 			}
-			if tk_list[tk_list.len() - 1] != Token::TrightParen {
-				panic!("Missing Right Parentesis.")
+			if args.iter().any(|arg| arg.is_empty() || !is_key_word(arg)) {
+				panic!("Every param of {} must be TyExprC", name)
 			}
-
-            /* eqC(x, y) */
-			if tk_list.len() == 6 && tk_list[3] == Token::Tcomma {
-                let temp = tk_list[2].clone();
-				let first: &[Token] = &vec![temp];
-                let temp = tk_list[4].clone();
-				let second: &[Token] = &vec![temp];
-				AST::AeqC(Box::new(parse(first)), Box::new(parse(second)))
-			} else if tk_list.len() == 12 && tk_list[6] == Token::Tcomma {
-				let first: &[Token] = &tk_list[2..6];
-				let second: &[Token] = &tk_list[7..(tk_list.len() - 1)];
-				AST::AeqC(Box::new(parse(first)), Box::new(parse(second)))
-			} else /* there are things in first and second param */ {
-                let start_position = 2;
-                let mut end_position = 2;
-                for (index, token) in tk_list.iter().enumerate() {
-                    if *token == Token::Tcomma {
-                        if is_key_word(&tk_list[start_position..index]) {
-                            end_position = index;
-                            break;
-                        }
-                    }
-                }
-                if tk_list.len() < (end_position + 3) {
-                    panic!("Invalid format of eqC.")
-                } else if start_position == end_position || (end_position + 1) == (tk_list.len() - 1) {
-                    panic!("Parsing Error Occurred!")
-                } else {
-                    let first: &[Token] = &tk_list[start_position..end_position];
-                    let second: &[Token] = &tk_list[(end_position + 1) .. tk_list.len() - 1];
-                    AST::AeqC(Box::new(parse(first)), Box::new(parse(second)))
-                }
-            }
-		} /* [END] Token::TeqC */ ,
+			AST::AprimC(name, args.into_iter().map(parse).collect())
+		} /* [END] Token::TprimC */ ,
 
         Token::TifC => {
             if tk_list.len() < 8 {
@@ -601,7 +1061,7 @@ pub fn parse(tk_list: &[Token]) -> AST {
 
 
         Token::TfdC => {
-            if tk_list.len() < 10 {
+            if tk_list.len() < 8 {
                 panic!("Incorrect format of fdC.")
             }
             if tk_list[1] != Token::TleftParen {
@@ -611,35 +1071,55 @@ pub fn parse(tk_list: &[Token]) -> AST {
                 panic!("Missing Right Parenthesis.")
             }
 
+            let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+
+            /* fdC("x", body): both types come from the expected function type */
+            if args.len() == 2 && is_name(args[0]) && !args[1].is_empty() && is_key_word(args[1]) {
+                let name = get_name(&args[0][1]);
+                return AST::AfdC(name, Box::new(Type::BlankT), Box::new(Type::BlankT), Box::new(Type::BlankT), Box::new(parse(args[1])))
+            }
+            if args.len() != 4 && args.len() != 5 {
+                panic!("Incorrect format of fdC.")
+            }
+
             /* verify the first param is String type */
-            if tk_list[2] != Token::Tquote || tk_list[4] != Token::Tquote || tk_list[5] != Token::Tcomma {
+            if !is_name(args[0]) {
                 panic!("First param must be String (Format Error)")
             }
 
             /* verify the second param is Type */
-            if !is_type(&tk_list[6]) || tk_list[7] != Token::Tcomma {
+            if !is_type_expr(args[1]) {
                 panic!("Second param must be Type (Format Error)")
             }
 
             /* verify the third param is Type */
-            if !is_type(&tk_list[8]) || tk_list[9] != Token::Tcomma {
+            if !is_type_expr(args[2]) {
                 panic!("Third param must be Type (Format Error)")
             }
 
-            /* verify the forth param is TyExprC */
-            if !is_key_word(&tk_list[10..(tk_list.len() - 1)]) {
-                panic!("Forth param must be TyExprC")
+            /* fdC("x", T1, T2, totalT, body) declares the effect */
+            let effect = if args.len() == 5 {
+                match args[3] {
+                    [Token::Ttype(Type::TotalT)] => Type::TotalT,
+                    [Token::Ttype(Type::DivT)] => Type::DivT,
+                    _ => panic!("Forth param must be totalT or divT"),
+                }
+            } else {
+                Type::BlankT
+            };
+
+            /* verify the last param is TyExprC */
+            let body: &[Token] = args[args.len() - 1];
+            if body.is_empty() || !is_key_word(body) {
+                panic!("Last param must be TyExprC")
             }
 
-            let name = get_name(&tk_list[3].clone());
+            let name = get_name(&args[0][1]);
 
-            let arg_type = get_type(&tk_list[6].clone());
-            let ret_type = get_type(&tk_list[8].clone());
+            let arg_type = parse_type(args[1]);
+            let ret_type = parse_type(args[2]);
 
-            // println!("arg type {:?}", arg_type);
-            // println!("ret type {:?}", ret_type);
-            let body: &[Token] = &tk_list[10..(tk_list.len() - 1)];
-            AST::AfdC(name, Box::new(arg_type), Box::new(ret_type), Box::new(parse(body)))
+            AST::AfdC(name, Box::new(arg_type), Box::new(ret_type), Box::new(effect), Box::new(parse(body)))
         } /* [END] Token::TfdC */ ,
 
 		Token::TrecC => {
@@ -653,504 +1133,4614 @@ pub fn parse(tk_list: &[Token]) -> AST {
                 panic!("Missing Right Parenthesis.")
             }
 
+			let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+			if args.len() != 6 {
+				panic!("Need More Argument for recC.")
+			}
+
 			/* verify the first param is String type */
-            if tk_list[2] != Token::Tquote || tk_list[4] != Token::Tquote || tk_list[5] != Token::Tcomma {
+            if !is_name(args[0]) {
                 panic!("First param must be String (Format Error)")
             }
 
 			/* verify the second param is String type */
-			if tk_list[6] != Token::Tquote || tk_list[8] != Token::Tquote || tk_list[9] != Token::Tcomma {
+			if !is_name(args[1]) {
 				panic!("Second param must be String (Format Error)")
 			}
 
 			/* verify the third param is Type */
-            if !is_type(&tk_list[10]) || tk_list[11] != Token::Tcomma {
+            if !is_type_expr(args[2]) {
                 panic!("Third param must be Type (Format Error)")
             }
 
 			/* verify the forth param is Type */
-            if !is_type(&tk_list[12]) || tk_list[13] != Token::Tcomma {
+            if !is_type_expr(args[3]) {
                 panic!("Forth param must be Type (Format Error)")
             }
 
 			/* verify the fifth param is TyExprC */
-			let start_index = 14;
-			let mut end_index = 14;
-			for (index, token) in tk_list.iter().enumerate() {
-				if index < start_index { continue; }
-				if *token == Token::Tcomma {
-					if is_key_word(&tk_list[start_index..index]) {
-						end_index = index;
-						break;
-					}
-				}
-			}
-
-			if tk_list.len() < (end_index + 3) {
-				panic!("Need More Argument for recC.")
-			}
-			if start_index == end_index || (end_index + 1) == (tk_list.len() - 1) {
-				panic!("Parsing Error Occurred!")
-			}
-
-			if !is_key_word(&tk_list[start_index..end_index]) {
+			if args[4].is_empty() || !is_key_word(args[4]) {
 				panic!("Fifth param must be TyExprC")
 			}
 
-			if !is_key_word(&tk_list[(end_index + 1) .. (tk_list.len() - 1)]) {
+			if args[5].is_empty() || !is_key_word(args[5]) {
 				panic!("Last param must be TyExprC")
 			}
 
-			let name1 = get_name(&tk_list[3].clone());
-			let name2 = get_name(&tk_list[7].clone());
-			let type1 = get_type(&tk_list[10].clone());
-			let type2 = get_type(&tk_list[12].clone());
-			let body1: &[Token] = &tk_list[start_index..end_index];
-			let body2: &[Token] = &tk_list[(end_index + 1) .. (tk_list.len() - 1)];
+			let name1 = get_name(&args[0][1]);
+			let name2 = get_name(&args[1][1]);
+			let type1 = parse_type(args[2]);
+			let type2 = parse_type(args[3]);
+			let body1: &[Token] = args[4];
+			let body2: &[Token] = args[5];
 			AST::ArecC(name1, name2, Box::new(type1), Box::new(type2), Box::new(parse(body1)), Box::new(parse(body2)))
 		} /* [END] Token::TTrecC */ ,
-	} /* [END] match statement */
 
-} /* [END] parse function */
+		Token::TletrecC => {
+			if tk_list.len() < 4 {
+				panic!("Incorrect format of letrecC.")
+			}
+			if tk_list[1] != Token::TleftParen {
+                panic!("Missing Left Parenthesis.")
+            }
+            if tk_list[tk_list.len() - 1] != Token::TrightParen {
+                panic!("Missing Right Parenthesis.")
+            }
 
-pub fn get_name(token: &Token) -> String {
-    match token {
-        Token::Tid(s) => s.to_string(),
-        _ => "".to_string()
-    }
-}
+			/* letrecC((f, x, T1, T2, body), ..., body) */
+			let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+			if args.len() < 2 {
+				panic!("letrecC needs at least one binding and a body.")
+			}
+			let mut bindings = Vec::new();
+			for binding in &args[0..(args.len() - 1)] {
+				if !is_rec_binding(binding) {
+					panic!("Binding of letrecC must be (\"name\", \"param\", Type, Type, TyExprC)")
+				}
+				bindings.push(parse_rec_binding(binding));
+			}
 
-pub fn get_type(token: &Token) -> Type {
-    match token {
-        Token::Ttype(t) => t.clone(),
-        _ => panic!("Not a valid type")
-    }
-}
+			let body = args[args.len() - 1];
+			if !is_key_word(body) {
+				panic!("Last param must be TyExprC")
+			}
+			AST::AletrecC(bindings, Box::new(parse(body)))
+		} /* [END] Token::TletrecC */ ,
 
-pub fn is_key_word(tk_list: &[Token]) -> bool {
-    // println!("Parameter: {:?}", tk_list);
-    let check_first = tk_list[0].clone();
-    match check_first {
-        Token::Tnum(_i) => {
-            if tk_list.len() == 1 {
-                true
-            } else {
-                false
+		Token::TletC => {
+			if tk_list.len() < 10 {
+				panic!("Incorrect format of letC.")
+			}
+			if tk_list[1] != Token::TleftParen {
+                panic!("Missing Left Parenthesis.")
             }
-        },
-        Token::Tid(_s) => {
-            if tk_list.len() == 1 {
-                true
-            } else {
-                false
+            if tk_list[tk_list.len() - 1] != Token::TrightParen {
+                panic!("Missing Right Parenthesis.")
             }
-        },
-        Token::Ttype(_t) => false,
-        Token::TtrueC | Token::TfalseC => {
-            if tk_list.len() == 1 {
-                true
-            } else {
-                false
+
+			/* letC("x", rhs, body) */
+			let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+			if args.len() != 3 {
+				panic!("letC needs a name, a TyExprC and a body.")
+			}
+			if !is_name(args[0]) {
+				panic!("First param must be String (Format Error)")
+			}
+			if args[1].is_empty() || !is_key_word(args[1]) {
+				panic!("Second param must be TyExprC")
+			}
+			if args[2].is_empty() || !is_key_word(args[2]) {
+				panic!("Last param must be TyExprC")
+			}
+			AST::AletC(get_name(&args[0][1]), Box::new(parse(args[1])), Box::new(parse(args[2])))
+		} /* [END] Token::TletC */ ,
+
+		Token::TtyLamC => {
+			if tk_list.len() < 8 {
+				panic!("Incorrect format of tyLamC.")
+			}
+			if tk_list[1] != Token::TleftParen {
+                panic!("Missing Left Parenthesis.")
             }
-        },
-        Token::TleftParen | Token::TrightParen | Token::Tcomma | Token::Tquote => { false },
-        Token::TnumC => {
-            if tk_list.len() == 4 {
-                true
-            } else {
-                false
+            if tk_list[tk_list.len() - 1] != Token::TrightParen {
+                panic!("Missing Right Parenthesis.")
             }
-        },
-        Token::TidC => {
-            if tk_list.len() == 4 || tk_list.len() == 6 {
-                true
-            } else {
-                false
+
+			/* tyLamC("a", body) */
+			let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+			if args.len() != 2 || !is_name(args[0]) {
+				panic!("First param must be String (Format Error)")
+			}
+			if args[1].is_empty() || !is_key_word(args[1]) {
+				panic!("Last param must be TyExprC")
+			}
+			AST::AtyLamC(get_name(&args[0][1]), Box::new(parse(args[1])))
+		} /* [END] Token::TtyLamC */ ,
+
+		Token::TtyAppC => {
+			if tk_list.len() < 6 {
+				panic!("Incorrect format of tyAppC.")
+			}
+			if tk_list[1] != Token::TleftParen {
+                panic!("Missing Left Parenthesis.")
             }
-        }
-        Token::TplusC | Token::TmultC | Token::TeqC | Token::TappC => {
-            if tk_list.len() < 6 {
-                false
-            } else if tk_list[1] != Token::TleftParen {
-				false
-			} else if tk_list[tk_list.len() - 1] != Token::TrightParen {
-				false
-			} else {
-                let start_index = 2;
-                let mut end_index = 2;
-                for (index, token) in tk_list.iter().enumerate() {
-                    if *token == Token::Tcomma && is_key_word(&tk_list[start_index..index]){
-                        end_index = index;
-                        break;
-                    }
-                }
-                if tk_list.len() < (end_index + 3) {
-                    false
-                } else if start_index == end_index || (end_index + 1) == (tk_list.len() - 1) {
-                    false
-                } else {
-                    if !is_key_word(&tk_list[start_index..end_index]) {
-                        false
-                    } else if !is_key_word(&tk_list[(end_index + 1) .. (tk_list.len() - 1)]) {
-                        false
-                    } else {
-                        true
-                    }
-                }
+            if tk_list[tk_list.len() - 1] != Token::TrightParen {
+                panic!("Missing Right Parenthesis.")
             }
-        },
-        Token::TifC => {
-            if tk_list.len() < 8 {
-                false
-            } else if tk_list[1] != Token::TleftParen {
-				false
-			} else if tk_list[tk_list.len() - 1] != Token::TrightParen {
-				false
-			} else {
-                let first_start_index = 2;
-                let mut first_end_index = 2;
-                for (index, token) in tk_list.iter().enumerate() {
-                    if *token == Token::Tcomma && is_key_word(&tk_list[first_start_index..index]){
-                        first_end_index = index;
-                        break;
-                    }
-                }
-                if tk_list.len() < (first_end_index + 3) {
-                    false
-                } else if first_start_index == first_end_index {
-                    false
-                } else {
-                    let second_start_index = first_end_index + 1;
-                    let mut second_end_index = first_end_index + 1;
-                    for (index, token) in tk_list.iter().enumerate() {
-                        if index < second_start_index { continue; }
-                        if *token == Token::Tcomma && is_key_word(&tk_list[second_start_index..index]) {
-                            second_end_index = index;
-                            break;
-                        }
-                    }
-                    if tk_list.len() < (second_end_index + 3) {
-                        false
-                    } else if second_start_index == second_end_index {
-                        false
-                    } else {
-                        if !is_key_word(&tk_list[first_start_index..first_end_index]) {
-                            false
-                        } else if !is_key_word(&tk_list[second_start_index..second_end_index]) {
-                            false
-                        } else if !is_key_word(&tk_list[(second_end_index + 1) .. (tk_list.len() - 1)]) {
-                            false
-                        } else {
-                            true
-                        }
-                    }
-                }
+
+			/* tyAppC(expr, Type) */
+			let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+			if args.len() != 2 || args[0].is_empty() || !is_key_word(args[0]) {
+				panic!("First param must be TyExprC")
+			}
+			if !is_type_expr(args[1]) {
+				panic!("Second param must be Type (Format Error)")
+			}
+			AST::AtyAppC(Box::new(parse(args[0])), parse_type(args[1]))
+		} /* [END] Token::TtyAppC */ ,
+
+		Token::TtupleC => {
+			if tk_list.len() < 4 {
+				panic!("Incorrect format of tupleC.")
+			}
+			if tk_list[1] != Token::TleftParen {
+                panic!("Missing Left Parenthesis.")
             }
-        } /* [END] ifC */ ,
-        Token::TfdC => {
-            if tk_list.len() < 10 {
-				false
-			} else if tk_list[1] != Token::TleftParen {
-				false
-			} else if tk_list[tk_list.len() - 1] != Token::TrightParen {
-				false
-			} else if tk_list[2] != Token::Tquote || tk_list[4] != Token::Tquote || tk_list[5] != Token::Tcomma {
-				false
-			} else if !is_type(&tk_list[6]) || tk_list[7] != Token::Tcomma {
-				false
-			} else if !is_type(&tk_list[8]) || tk_list[9] != Token::Tcomma {
-				false
-			} else if !is_key_word(&tk_list[10..(tk_list.len() - 1)]) {
-				false
-			} else {
-				true
+            if tk_list[tk_list.len() - 1] != Token::TrightParen {
+                panic!("Missing Right Parenthesis.")
+            }
+
+			/* tupleC(e1, ..., en) */
+			let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+			let mut items = Vec::new();
+			for arg in args {
+				if arg.is_empty() || !is_key_word(arg) {
+					panic!("Every param of tupleC must be TyExprC")
+				}
+				items.push(parse(arg));
 			}
-        },
-		Token::TrecC => {
-			if tk_list.len() < 18 {
-				false
-			} else if tk_list[1] != Token::TleftParen {
-				false
-			} else if tk_list[tk_list.len() - 1] != Token::TrightParen {
-				false
-			} else if tk_list[2] != Token::Tquote || tk_list[4] != Token::Tquote || tk_list[5] != Token::Tcomma {
-				false
-			} else if tk_list[6] != Token::Tquote || tk_list[8] != Token::Tquote || tk_list[9] != Token::Tcomma {
-				false
-			} else if !is_type(&tk_list[10]) || tk_list[11] != Token::Tcomma {
-				false
-			} else if !is_type(&tk_list[12]) || tk_list[13] != Token::Tcomma {
-				false
-			} else {
-                let start_index = 14;
-                let mut end_index = 14;
-                for (index, token) in tk_list.iter().enumerate() {
-					if index < start_index { continue; }
-                    if *token == Token::Tcomma && is_key_word(&tk_list[start_index..index]) {
-                        end_index = index;
-                        break;
-                    }
-                }
-                if tk_list.len() < (end_index + 3) {
-                    false
-                } else if start_index == end_index || (end_index + 1) == (tk_list.len() - 1) {
-                    false
-                } else {
-                    if !is_key_word(&tk_list[start_index..end_index]) {
-                        false
-                    } else if !is_key_word(&tk_list[(end_index + 1) .. (tk_list.len() - 1)]) {
-                        false
-                    } else {
-                        true
-                    }
-                }
+			AST::AtupleC(items)
+		} /* [END] Token::TtupleC */ ,
+
+		Token::TprojC => {
+			if tk_list.len() < 6 {
+				panic!("Incorrect format of projC.")
+			}
+			if tk_list[1] != Token::TleftParen {
+                panic!("Missing Left Parenthesis.")
             }
-		}
+            if tk_list[tk_list.len() - 1] != Token::TrightParen {
+                panic!("Missing Right Parenthesis.")
+            }
+
+			/* projC(expr, index), the index starts at 0 */
+			let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+			if args.len() != 2 || args[0].is_empty() || !is_key_word(args[0]) {
+				panic!("First param must be TyExprC")
+			}
+			match args[1] {
+				[Token::Tnum(i)] if *i >= 0 => AST::AprojC(Box::new(parse(args[0])), *i as usize),
+				_ => panic!("Second param must be a Number"),
+			}
+		} /* [END] Token::TprojC */ ,
+
+		Token::TinlC | Token::TinrC => {
+			if tk_list.len() < 6 {
+				panic!("Incorrect format of inlC / inrC.")
+			}
+			if tk_list[1] != Token::TleftParen {
+                panic!("Missing Left Parenthesis.")
+            }
+            if tk_list[tk_list.len() - 1] != Token::TrightParen {
+                panic!("Missing Right Parenthesis.")
+            }
+
+			/* inlC(expr, sumT(T, T)) */
+			let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+			if args.len() != 2 || args[0].is_empty() || !is_key_word(args[0]) {
+				panic!("First param must be TyExprC")
+			}
+			if !is_type_expr(args[1]) {
+				panic!("Second param must be Type (Format Error)")
+			}
+			match tk_list[0] {
+				Token::TinlC => AST::AinlC(Box::new(parse(args[0])), parse_type(args[1])),
+				_ => AST::AinrC(Box::new(parse(args[0])), parse_type(args[1])),
+			}
+		} /* [END] Token::TinlC | Token::TinrC */ ,
+
+		Token::TcaseC => {
+			if tk_list.len() < 16 {
+				panic!("Incorrect format of caseC.")
+			}
+			if tk_list[1] != Token::TleftParen {
+                panic!("Missing Left Parenthesis.")
+            }
+            if tk_list[tk_list.len() - 1] != Token::TrightParen {
+                panic!("Missing Right Parenthesis.")
+            }
+
+			/* caseC(expr, "x", left-body, "y", right-body) */
+			let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+			if args.len() != 5 {
+				panic!("Incorrect format of caseC.")
+			}
+			if args[0].is_empty() || !is_key_word(args[0]) {
+				panic!("First param must be TyExprC")
+			}
+			if !is_name(args[1]) || !is_name(args[3]) {
+				panic!("Second and forth param must be String (Format Error)")
+			}
+			if args[2].is_empty() || !is_key_word(args[2]) || args[4].is_empty() || !is_key_word(args[4]) {
+				panic!("Third and fifth param must be TyExprC")
+			}
+			AST::AcaseC(Box::new(parse(args[0])), get_name(&args[1][1]), Box::new(parse(args[2])), get_name(&args[3][1]), Box::new(parse(args[4])))
+		} /* [END] Token::TcaseC */ ,
+
+		Token::TemptyC => {
+			if tk_list.len() < 4 {
+				panic!("Incorrect format of emptyC.")
+			}
+			if tk_list[1] != Token::TleftParen {
+                panic!("Missing Left Parenthesis.")
+            }
+            if tk_list[tk_list.len() - 1] != Token::TrightParen {
+                panic!("Missing Right Parenthesis.")
+            }
+
+			/* emptyC(Type), the type of the elements */
+			let elem_type: &[Token] = &tk_list[2..(tk_list.len() - 1)];
+			if !is_type_expr(elem_type) {
+				panic!("Param of emptyC must be Type (Format Error)")
+			}
+			AST::AemptyC(parse_type(elem_type))
+		} /* [END] Token::TemptyC */ ,
+
+		Token::TconsC => {
+			if tk_list.len() < 6 {
+				panic!("Incorrect format of consC.")
+			}
+			if tk_list[1] != Token::TleftParen {
+                panic!("Missing Left Parenthesis.")
+            }
+            if tk_list[tk_list.len() - 1] != Token::TrightParen {
+                panic!("Missing Right Parenthesis.")
+            }
+
+			/* consC(first, rest) */
+			let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+			if args.len() != 2 || args.iter().any(|arg| arg.is_empty() || !is_key_word(arg)) {
+				panic!("Both params of consC must be TyExprC")
+			}
+			AST::AconsC(Box::new(parse(args[0])), Box::new(parse(args[1])))
+		} /* [END] Token::TconsC */ ,
+
+		Token::TfirstC | Token::TrestC | Token::TisEmptyC => {
+			if tk_list.len() < 4 {
+				panic!("Incorrect format of list operation.")
+			}
+			if tk_list[1] != Token::TleftParen {
+                panic!("Missing Left Parenthesis.")
+            }
+            if tk_list[tk_list.len() - 1] != Token::TrightParen {
+                panic!("Missing Right Parenthesis.")
+            }
+
+			let list: &[Token] = &tk_list[2..(tk_list.len() - 1)];
+			if !is_key_word(list) {
+				panic!("Param of list operation must be TyExprC")
+			}
+			match tk_list[0] {
+				Token::TfirstC => AST::AfirstC(Box::new(parse(list))),
+				Token::TrestC => AST::ArestC(Box::new(parse(list))),
+				_ => AST::AisEmptyC(Box::new(parse(list))),
+			}
+		} /* [END] Token::TfirstC | Token::TrestC | Token::TisEmptyC */ ,
+
+		Token::TrecordC => {
+			if tk_list.len() < 3 {
+				panic!("Incorrect format of recordC.")
+			}
+			if tk_list[1] != Token::TleftParen {
+                panic!("Missing Left Parenthesis.")
+            }
+            if tk_list[tk_list.len() - 1] != Token::TrightParen {
+                panic!("Missing Right Parenthesis.")
+            }
+
+			/* recordC(("x", e1), ("y", e2), ...) */
+			let mut fields = Vec::new();
+			if tk_list.len() > 3 {
+				for field in split_args(&tk_list[2..(tk_list.len() - 1)]) {
+					if !is_field(field, is_key_word) {
+						panic!("Field of recordC must be (\"name\", TyExprC)")
+					}
+					let (name, value) = split_field(field);
+					fields.push((name, parse(value)));
+				}
+			}
+			AST::ArecordC(fields)
+		} /* [END] Token::TrecordC */ ,
+
+		Token::TgetC => {
+			if tk_list.len() < 8 {
+				panic!("Incorrect format of getC.")
+			}
+			if tk_list[1] != Token::TleftParen {
+                panic!("Missing Left Parenthesis.")
+            }
+            if tk_list[tk_list.len() - 1] != Token::TrightParen {
+                panic!("Missing Right Parenthesis.")
+            }
+
+			/* getC(expr, "x") */
+			let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+			if args.len() != 2 || args[0].is_empty() || !is_key_word(args[0]) {
+				panic!("First param must be TyExprC")
+			}
+			if !is_name(args[1]) {
+				panic!("Second param must be String (Format Error)")
+			}
+			AST::AgetC(Box::new(parse(args[0])), get_name(&args[1][1]))
+		} /* [END] Token::TgetC */ ,
+
+		Token::TdatatypeC => {
+			if tk_list.len() < 8 {
+				panic!("Incorrect format of datatypeC.")
+			}
+			if tk_list[1] != Token::TleftParen {
+                panic!("Missing Left Parenthesis.")
+            }
+            if tk_list[tk_list.len() - 1] != Token::TrightParen {
+                panic!("Missing Right Parenthesis.")
+            }
+
+			/* datatypeC("Name", ("Variant", T, ...), ..., body) */
+			let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+			if args.len() < 3 {
+				panic!("datatypeC needs a name, at least one variant and a body.")
+			}
+			if !is_name(args[0]) {
+				panic!("First param must be String (Format Error)")
+			}
+			let mut variants = Vec::new();
+			for variant in &args[1..(args.len() - 1)] {
+				if !is_variant(variant) {
+					panic!("Variant of datatypeC must be (\"Name\", Type, ...)")
+				}
+				let fields = split_args(&variant[1..(variant.len() - 1)]);
+				variants.push((get_name(&fields[0][1]), fields[1..].iter().map(|field| parse_type(field)).collect()));
+			}
+			let body = args[args.len() - 1];
+			if body.is_empty() || !is_key_word(body) {
+				panic!("Last param must be TyExprC")
+			}
+			AST::AdatatypeC(get_name(&args[0][1]), variants, Box::new(parse(body)))
+		} /* [END] Token::TdatatypeC */ ,
+
+		Token::TconC => {
+			if tk_list.len() < 6 {
+				panic!("Incorrect format of conC.")
+			}
+			if tk_list[1] != Token::TleftParen {
+                panic!("Missing Left Parenthesis.")
+            }
+            if tk_list[tk_list.len() - 1] != Token::TrightParen {
+                panic!("Missing Right Parenthesis.")
+            }
+
+			/* conC("Variant", e1, ..., en) */
+			let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+			if !is_name(args[0]) {
+				panic!("First param must be String (Format Error)")
+			}
+			let mut fields = Vec::new();
+			for arg in &args[1..] {
+				if arg.is_empty() || !is_key_word(arg) {
+					panic!("Field of conC must be TyExprC")
+				}
+				fields.push(parse(arg));
+			}
+			AST::AconC(get_name(&args[0][1]), fields)
+		} /* [END] Token::TconC */ ,
+
+		Token::TmatchC => {
+			if tk_list.len() < 8 {
+				panic!("Incorrect format of matchC.")
+			}
+			if tk_list[1] != Token::TleftParen {
+                panic!("Missing Left Parenthesis.")
+            }
+            if tk_list[tk_list.len() - 1] != Token::TrightParen {
+                panic!("Missing Right Parenthesis.")
+            }
+
+			/* matchC(expr, ("Variant", "x", ..., body), ..., (_, body)) */
+			let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+			if args.len() < 2 || args[0].is_empty() || !is_key_word(args[0]) {
+				panic!("First param must be TyExprC")
+			}
+			let mut arms = Vec::new();
+			for arm in &args[1..] {
+				if !is_arm(arm) {
+					panic!("Arm of matchC must be (\"Variant\", \"x\", ..., TyExprC) or (_, TyExprC)")
+				}
+				let parts = split_args(&arm[1..(arm.len() - 1)]);
+				let variant = match parts[0] {
+					[Token::Ttype(Type::BlankT)] => None,
+					_ => Some(get_name(&parts[0][1])),
+				};
+				let vars = parts[1..(parts.len() - 1)].iter().map(|var| match var {
+					[Token::Ttype(Type::BlankT)] => "_".to_string(),
+					_ => get_name(&var[1]),
+				}).collect();
+				arms.push((variant, vars, parse(parts[parts.len() - 1])));
+			}
+			AST::AmatchC(Box::new(parse(args[0])), arms)
+		} /* [END] Token::TmatchC */ ,
+
+		Token::TfoldC => {
+			if tk_list.len() < 6 {
+				panic!("Incorrect format of foldC.")
+			}
+			if tk_list[1] != Token::TleftParen {
+                panic!("Missing Left Parenthesis.")
+            }
+            if tk_list[tk_list.len() - 1] != Token::TrightParen {
+                panic!("Missing Right Parenthesis.")
+            }
+
+			/* foldC(muT("t", T), expr) */
+			let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+			if args.len() != 2 || !is_type_expr(args[0]) {
+				panic!("First param must be Type (Format Error)")
+			}
+			if args[1].is_empty() || !is_key_word(args[1]) {
+				panic!("Second param must be TyExprC")
+			}
+			AST::AfoldC(parse_type(args[0]), Box::new(parse(args[1])))
+		} /* [END] Token::TfoldC */ ,
+
+		Token::TunfoldC => {
+			if tk_list.len() < 4 {
+				panic!("Incorrect format of unfoldC.")
+			}
+			if tk_list[1] != Token::TleftParen {
+                panic!("Missing Left Parenthesis.")
+            }
+            if tk_list[tk_list.len() - 1] != Token::TrightParen {
+                panic!("Missing Right Parenthesis.")
+            }
+
+			let expr: &[Token] = &tk_list[2..(tk_list.len() - 1)];
+			if !is_key_word(expr) {
+				panic!("Param of unfoldC must be TyExprC")
+			}
+			AST::AunfoldC(Box::new(parse(expr)))
+		} /* [END] Token::TunfoldC */ ,
+
+		Token::TboxC | Token::TunboxC => {
+			if tk_list.len() < 4 {
+				panic!("Incorrect format of boxC / unboxC.")
+			}
+			if tk_list[1] != Token::TleftParen {
+                panic!("Missing Left Parenthesis.")
+            }
+            if tk_list[tk_list.len() - 1] != Token::TrightParen {
+                panic!("Missing Right Parenthesis.")
+            }
+
+			let expr: &[Token] = &tk_list[2..(tk_list.len() - 1)];
+			if !is_key_word(expr) {
+				panic!("Param of boxC / unboxC must be TyExprC")
+			}
+			match tk_list[0] {
+				Token::TboxC => AST::AboxC(Box::new(parse(expr))),
+				_ => AST::AunboxC(Box::new(parse(expr))),
+			}
+		} /* [END] Token::TboxC | Token::TunboxC */ ,
+
+		Token::TsetboxC => {
+			if tk_list.len() < 6 {
+				panic!("Incorrect format of setboxC.")
+			}
+			if tk_list[1] != Token::TleftParen {
+                panic!("Missing Left Parenthesis.")
+            }
+            if tk_list[tk_list.len() - 1] != Token::TrightParen {
+                panic!("Missing Right Parenthesis.")
+            }
+
+			/* setboxC(box, value) */
+			let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+			if args.len() != 2 || args.iter().any(|arg| arg.is_empty() || !is_key_word(arg)) {
+				panic!("Both params of setboxC must be TyExprC")
+			}
+			AST::AsetboxC(Box::new(parse(args[0])), Box::new(parse(args[1])))
+		} /* [END] Token::TsetboxC */ ,
+
+		Token::TseqC => {
+			if tk_list.len() < 4 {
+				panic!("Incorrect format of seqC.")
+			}
+			if tk_list[1] != Token::TleftParen {
+                panic!("Missing Left Parenthesis.")
+            }
+            if tk_list[tk_list.len() - 1] != Token::TrightParen {
+                panic!("Missing Right Parenthesis.")
+            }
+
+			/* seqC(e1, ..., en) */
+			let mut exprs = Vec::new();
+			for arg in split_args(&tk_list[2..(tk_list.len() - 1)]) {
+				if arg.is_empty() || !is_key_word(arg) {
+					panic!("Every param of seqC must be TyExprC")
+				}
+				exprs.push(parse(arg));
+			}
+			AST::AseqC(exprs)
+		} /* [END] Token::TseqC */ ,
+
+		Token::TasC => {
+			if tk_list.len() < 6 {
+				panic!("Incorrect format of asC.")
+			}
+			if tk_list[1] != Token::TleftParen {
+                panic!("Missing Left Parenthesis.")
+            }
+            if tk_list[tk_list.len() - 1] != Token::TrightParen {
+                panic!("Missing Right Parenthesis.")
+            }
+
+			/* asC(expr, T) */
+			let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+			if args.len() != 2 || args[0].is_empty() || !is_key_word(args[0]) {
+				panic!("First param must be TyExprC")
+			}
+			if !is_type_expr(args[1]) {
+				panic!("Second param must be Type (Format Error)")
+			}
+			AST::AasC(Box::new(parse(args[0])), parse_type(args[1]))
+		} /* [END] Token::TasC */ ,
+
+		Token::TholeC => {
+			/* holeC("name") */
+			if tk_list.len() != 6 || tk_list[1] != Token::TleftParen || tk_list[tk_list.len() - 1] != Token::TrightParen {
+				panic!("Incorrect format of holeC.")
+			}
+			if !is_name(&tk_list[2..5]) {
+				panic!("Param of holeC must be a name")
+			}
+			AST::AholeC(get_name(&tk_list[3]))
+		} /* [END] Token::TholeC */ ,
+
+		Token::TfunT | Token::TforallT | Token::TmuT | Token::TtupleT | Token::TsumT | Token::TlistT | Token::TrecordT | Token::TnamedT | Token::TrefT => AST::AtypeC(parse_type(tk_list)),
+	} /* [END] match statement */
+
+} /* [END] parse function */
+
+pub fn get_name(token: &Token) -> String {
+    match token {
+        Token::Tid(s) => s.to_string(),
+        _ => "".to_string()
+    }
+}
+
+pub fn get_type(token: &Token) -> Type {
+    match token {
+        Token::Ttype(t) => t.clone(),
+        _ => panic!("Not a valid type")
+    }
+}
+
+/* split the tokens between two parentheses at the commas that are not nested */
+pub fn split_args(tk_list: &[Token]) -> Vec<&[Token]> {
+	let mut args = Vec::new();
+	let mut depth = 0;
+	let mut start = 0;
+	for (index, token) in tk_list.iter().enumerate() {
+		match token {
+			Token::TleftParen => depth += 1,
+			Token::TrightParen => depth -= 1,
+			Token::Tcomma if depth == 0 => {
+				args.push(&tk_list[start..index]);
+				start = index + 1;
+			},
+			_ => {},
+		}
+	}
+	args.push(&tk_list[start..]);
+	args
+}
+
+/* ("name", "param", Type, Type, TyExprC) */
+pub fn is_rec_binding(tk_list: &[Token]) -> bool {
+	if tk_list.len() < 14 || tk_list[0] != Token::TleftParen || tk_list[tk_list.len() - 1] != Token::TrightParen {
+		return false;
+	}
+	let args = split_args(&tk_list[1..(tk_list.len() - 1)]);
+	args.len() == 5
+		&& is_name(args[0])
+		&& is_name(args[1])
+		&& is_type_expr(args[2])
+		&& is_type_expr(args[3])
+		&& !args[4].is_empty() && is_key_word(args[4])
+}
+
+pub fn parse_rec_binding(tk_list: &[Token]) -> (String, String, Type, Type, AST) {
+	let args = split_args(&tk_list[1..(tk_list.len() - 1)]);
+	(get_name(&args[0][1]), get_name(&args[1][1]), parse_type(args[2]), parse_type(args[3]), parse(args[4]))
+}
+
+/* "name" */
+pub fn is_name(tk_list: &[Token]) -> bool {
+	tk_list.len() == 3 && tk_list[0] == Token::Tquote && tk_list[2] == Token::Tquote
+}
+
+/* "name" or _ */
+pub fn is_pattern_name(tk_list: &[Token]) -> bool {
+	is_name(tk_list) || matches!(tk_list, [Token::Ttype(Type::BlankT)])
+}
+
+/* ("Variant", Type, ..., Type) */
+pub fn is_variant(tk_list: &[Token]) -> bool {
+	if tk_list.len() < 5 || tk_list[0] != Token::TleftParen || tk_list[tk_list.len() - 1] != Token::TrightParen {
+		return false;
+	}
+	let args = split_args(&tk_list[1..(tk_list.len() - 1)]);
+	is_name(args[0]) && args[1..].iter().all(|arg| is_type_expr(arg))
+}
+
+/* ("Variant", "x", ..., TyExprC) or (_, TyExprC) */
+pub fn is_arm(tk_list: &[Token]) -> bool {
+	if tk_list.len() < 5 || tk_list[0] != Token::TleftParen || tk_list[tk_list.len() - 1] != Token::TrightParen {
+		return false;
+	}
+	let args = split_args(&tk_list[1..(tk_list.len() - 1)]);
+	let body = args[args.len() - 1];
+	args.len() >= 2
+		&& is_pattern_name(args[0])
+		&& (is_name(args[0]) || args.len() == 2)
+		&& args[1..(args.len() - 1)].iter().all(|arg| is_pattern_name(arg))
+		&& !body.is_empty() && is_key_word(body)
+}
+
+pub fn is_key_word(tk_list: &[Token]) -> bool {
+    // println!("Parameter: {:?}", tk_list);
+    let check_first = tk_list[0].clone();
+    match check_first {
+        Token::Tnum(_i) => {
+            if tk_list.len() == 1 {
+                true
+            } else {
+                false
+            }
+        },
+        Token::Tid(_s) => {
+            if tk_list.len() == 1 {
+                true
+            } else {
+                false
+            }
+        },
+        Token::Tstr(_s) | Token::Thole(_s) => tk_list.len() == 1,
+        Token::Ttype(_t) => false,
+        Token::TtrueC | Token::TfalseC => {
+            if tk_list.len() == 1 {
+                true
+            } else {
+                false
+            }
+        },
+        Token::TleftParen | Token::TrightParen | Token::Tcomma | Token::Tquote => { false },
+        Token::TnumC => {
+            if tk_list.len() == 4 {
+                true
+            } else {
+                false
+            }
+        },
+        Token::TidC => {
+            if tk_list.len() == 4 || tk_list.len() == 6 {
+                true
+            } else {
+                false
+            }
+        }
+        Token::TappC => {
+            if tk_list.len() < 6 {
+                false
+            } else if tk_list[1] != Token::TleftParen {
+				false
+			} else if tk_list[tk_list.len() - 1] != Token::TrightParen {
+				false
+			} else {
+                let start_index = 2;
+                let mut end_index = 2;
+                for (index, token) in tk_list.iter().enumerate() {
+                    if *token == Token::Tcomma && is_key_word(&tk_list[start_index..index]){
+                        end_index = index;
+                        break;
+                    }
+                }
+                if tk_list.len() < (end_index + 3) {
+                    false
+                } else if start_index == end_index || (end_index + 1) == (tk_list.len() - 1) {
+                    false
+                } else {
+                    if !is_key_word(&tk_list[start_index..end_index]) {
+                        false
+                    } else if !is_key_word(&tk_list[(end_index + 1) .. (tk_list.len() - 1)]) {
+                        false
+                    } else {
+                        true
+                    }
+                }
+            }
+        },
+        Token::TifC => {
+            if tk_list.len() < 8 {
+                false
+            } else if tk_list[1] != Token::TleftParen {
+				false
+			} else if tk_list[tk_list.len() - 1] != Token::TrightParen {
+				false
+			} else {
+                let first_start_index = 2;
+                let mut first_end_index = 2;
+                for (index, token) in tk_list.iter().enumerate() {
+                    if *token == Token::Tcomma && is_key_word(&tk_list[first_start_index..index]){
+                        first_end_index = index;
+                        break;
+                    }
+                }
+                if tk_list.len() < (first_end_index + 3) {
+                    false
+                } else if first_start_index == first_end_index {
+                    false
+                } else {
+                    let second_start_index = first_end_index + 1;
+                    let mut second_end_index = first_end_index + 1;
+                    for (index, token) in tk_list.iter().enumerate() {
+                        if index < second_start_index { continue; }
+                        if *token == Token::Tcomma && is_key_word(&tk_list[second_start_index..index]) {
+                            second_end_index = index;
+                            break;
+                        }
+                    }
+                    if tk_list.len() < (second_end_index + 3) {
+                        false
+                    } else if second_start_index == second_end_index {
+                        false
+                    } else {
+                        if !is_key_word(&tk_list[first_start_index..first_end_index]) {
+                            false
+                        } else if !is_key_word(&tk_list[second_start_index..second_end_index]) {
+                            false
+                        } else if !is_key_word(&tk_list[(second_end_index + 1) .. (tk_list.len() - 1)]) {
+                            false
+                        } else {
+                            true
+                        }
+                    }
+                }
+            }
+        } /* [END] ifC */ ,
+        Token::TfdC => {
+			tk_list.len() >= 8 && tk_list[1] == Token::TleftParen && tk_list[tk_list.len() - 1] == Token::TrightParen && {
+				let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+				(args.len() == 2 && is_name(args[0]) && !args[1].is_empty() && is_key_word(args[1]))
+					|| ((args.len() == 4 || (args.len() == 5 && matches!(args[3], [Token::Ttype(Type::TotalT | Type::DivT)])))
+					&& is_name(args[0])
+					&& is_type_expr(args[1])
+					&& is_type_expr(args[2])
+					&& !args[args.len() - 1].is_empty() && is_key_word(args[args.len() - 1]))
+			}
+        },
+		Token::TrecC => {
+			if tk_list.len() < 18 {
+				false
+			} else if tk_list[1] != Token::TleftParen {
+				false
+			} else if tk_list[tk_list.len() - 1] != Token::TrightParen {
+				false
+			} else {
+				let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+				args.len() == 6
+					&& is_name(args[0])
+					&& is_name(args[1])
+					&& is_type_expr(args[2])
+					&& is_type_expr(args[3])
+					&& !args[4].is_empty() && is_key_word(args[4])
+					&& !args[5].is_empty() && is_key_word(args[5])
+			}
+		}
+		Token::TletrecC => {
+			if tk_list.len() < 4 || tk_list[1] != Token::TleftParen || tk_list[tk_list.len() - 1] != Token::TrightParen {
+				false
+			} else {
+				let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+				let body = args[args.len() - 1];
+				args.len() >= 2
+					&& args[0..(args.len() - 1)].iter().all(|binding| is_rec_binding(binding))
+					&& !body.is_empty() && is_key_word(body)
+			}
+		}
+		Token::TletC => {
+			if tk_list.len() < 10 || tk_list[1] != Token::TleftParen || tk_list[tk_list.len() - 1] != Token::TrightParen {
+				false
+			} else {
+				let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+				args.len() == 3
+					&& is_name(args[0])
+					&& !args[1].is_empty() && is_key_word(args[1])
+					&& !args[2].is_empty() && is_key_word(args[2])
+			}
+		}
+		Token::TtyLamC | Token::TtyAppC => {
+			if tk_list.len() < 6 || tk_list[1] != Token::TleftParen || tk_list[tk_list.len() - 1] != Token::TrightParen {
+				false
+			} else {
+				let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+				match tk_list[0] {
+					Token::TtyLamC => args.len() == 2 && is_name(args[0]) && !args[1].is_empty() && is_key_word(args[1]),
+					_ => args.len() == 2 && !args[0].is_empty() && is_key_word(args[0]) && is_type_expr(args[1]),
+				}
+			}
+		}
+		Token::TtupleC | Token::TprojC => {
+			if tk_list.len() < 4 || tk_list[1] != Token::TleftParen || tk_list[tk_list.len() - 1] != Token::TrightParen {
+				false
+			} else {
+				let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+				match tk_list[0] {
+					Token::TtupleC => args.iter().all(|arg| !arg.is_empty() && is_key_word(arg)),
+					_ => args.len() == 2 && !args[0].is_empty() && is_key_word(args[0]) && matches!(args[1], [Token::Tnum(_)]),
+				}
+			}
+		}
+		Token::TinlC | Token::TinrC | Token::TcaseC => {
+			if tk_list.len() < 6 || tk_list[1] != Token::TleftParen || tk_list[tk_list.len() - 1] != Token::TrightParen {
+				false
+			} else {
+				let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+				match tk_list[0] {
+					Token::TcaseC => args.len() == 5
+						&& !args[0].is_empty() && is_key_word(args[0])
+						&& is_name(args[1]) && !args[2].is_empty() && is_key_word(args[2])
+						&& is_name(args[3]) && !args[4].is_empty() && is_key_word(args[4]),
+					_ => args.len() == 2 && !args[0].is_empty() && is_key_word(args[0]) && is_type_expr(args[1]),
+				}
+			}
+		}
+		Token::TemptyC | Token::TconsC | Token::TfirstC | Token::TrestC | Token::TisEmptyC => {
+			if tk_list.len() < 4 || tk_list[1] != Token::TleftParen || tk_list[tk_list.len() - 1] != Token::TrightParen {
+				false
+			} else {
+				let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+				match tk_list[0] {
+					Token::TemptyC => args.len() == 1 && is_type_expr(args[0]),
+					Token::TconsC => args.len() == 2 && args.iter().all(|arg| !arg.is_empty() && is_key_word(arg)),
+					_ => args.len() == 1 && is_key_word(args[0]),
+				}
+			}
+		}
+		Token::TrecordC | Token::TgetC => {
+			if tk_list.len() < 3 || tk_list[1] != Token::TleftParen || tk_list[tk_list.len() - 1] != Token::TrightParen {
+				false
+			} else if tk_list.len() == 3 {
+				matches!(tk_list[0], Token::TrecordC)
+			} else {
+				let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+				match tk_list[0] {
+					Token::TrecordC => args.iter().all(|field| is_field(field, is_key_word)),
+					_ => args.len() == 2 && !args[0].is_empty() && is_key_word(args[0]) && is_name(args[1]),
+				}
+			}
+		}
+		Token::TdatatypeC | Token::TconC | Token::TmatchC => {
+			if tk_list.len() < 6 || tk_list[1] != Token::TleftParen || tk_list[tk_list.len() - 1] != Token::TrightParen {
+				false
+			} else {
+				let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+				let last = args[args.len() - 1];
+				match tk_list[0] {
+					Token::TdatatypeC => args.len() >= 3
+						&& is_name(args[0])
+						&& args[1..(args.len() - 1)].iter().all(|variant| is_variant(variant))
+						&& !last.is_empty() && is_key_word(last),
+					Token::TconC => is_name(args[0]) && args[1..].iter().all(|arg| !arg.is_empty() && is_key_word(arg)),
+					_ => args.len() >= 2
+						&& !args[0].is_empty() && is_key_word(args[0])
+						&& args[1..].iter().all(|arm| is_arm(arm)),
+				}
+			}
+		}
+		Token::TfoldC | Token::TunfoldC => {
+			if tk_list.len() < 4 || tk_list[1] != Token::TleftParen || tk_list[tk_list.len() - 1] != Token::TrightParen {
+				false
+			} else {
+				let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+				match tk_list[0] {
+					Token::TfoldC => args.len() == 2 && is_type_expr(args[0]) && !args[1].is_empty() && is_key_word(args[1]),
+					_ => args.len() == 1 && is_key_word(args[0]),
+				}
+			}
+		}
+		Token::TholeC => tk_list.len() == 6 && tk_list[1] == Token::TleftParen && is_name(&tk_list[2..5]) && tk_list[5] == Token::TrightParen,
+		Token::TasC => {
+			if tk_list.len() < 6 || tk_list[1] != Token::TleftParen || tk_list[tk_list.len() - 1] != Token::TrightParen {
+				false
+			} else {
+				let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+				args.len() == 2 && !args[0].is_empty() && is_key_word(args[0]) && is_type_expr(args[1])
+			}
+		}
+		Token::TboxC | Token::TunboxC | Token::TsetboxC | Token::TseqC => {
+			if tk_list.len() < 4 || tk_list[1] != Token::TleftParen || tk_list[tk_list.len() - 1] != Token::TrightParen {
+				false
+			} else {
+				let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+				let all_exprs = args.iter().all(|arg| !arg.is_empty() && is_key_word(arg));
+				match tk_list[0] {
+					Token::TsetboxC => args.len() == 2 && all_exprs,
+					Token::TseqC => all_exprs,
+					_ => args.len() == 1 && all_exprs,
+				}
+			}
+		}
+		Token::TprimC(name) => {
+			if tk_list.len() < 4 || tk_list[1] != Token::TleftParen || tk_list[tk_list.len() - 1] != Token::TrightParen {
+				false
+			} else {
+				let args = split_args(&tk_list[2..(tk_list.len() - 1)]);
+				args.len() == primitive(name).unwrap().arity() && args.iter().all(|arg| !arg.is_empty() && is_key_word(arg))
+			}
+		}
+		Token::TfunT | Token::TforallT | Token::TmuT | Token::TtupleT | Token::TsumT | Token::TlistT | Token::TrecordT | Token::TnamedT | Token::TrefT => false,
+    }
+} /* [END] is_key_word function */
+
+/* ====================================================================================== */
+/* ====================================================================================== */
+/* ====================================================================================== */
+/* ====================================================================================== */
+/* ====================================================================================== */
+
+/* --------------- Type Checkier Function ---------------
+ * @parameter: an AST and a HashMap (type environment)
+ * @return: a Type
+ * Description: the function checks for the type. Missing annotations
+ *              are inferred, and the result is the principal type with
+ *              its type variables renamed to 'a, 'b, ...
+ */
+pub fn tc(ast: AST, tnv: &HashMap<String, Type>) -> Type {
+	tc_schemes(ast, tnv).0
+}
+
+/* same as tc, but also returns the scheme of every name bound by
+ * letC, recC and letrecC, in the order they were checked
+ */
+pub fn tc_schemes(ast: AST, tnv: &HashMap<String, Type>) -> (Type, Vec<(String, Type)>) {
+	let checked = check(ast, tnv);
+	(checked.result, checked.schemes)
+}
+
+/* everything the checker found: the type of the program, the schemes of
+ * the let-bound names, the holes left in it and the typed tree
+ */
+pub struct Checked {
+	pub result: Type,
+	pub schemes: Vec<(String, Type)>,
+	pub holes: Vec<Hole>,
+	pub tree: TypedNode,
+	pub program: AST, /* with a castC at every dynT boundary */
+}
+
+pub fn check(ast: AST, tnv: &HashMap<String, Type>) -> Checked {
+	let mut infer = Infer::new();
+	let result = infer.infer(&ast, &mut Env::from(tnv));
+	/* the effects still unknown are as small as their bounds allow */
+	let settled = infer.settle_effects().and_then(|_| {
+		effect_vars(&infer.resolve(&result)).into_iter().try_for_each(|v| infer.subeffect(&Type::VarT(v), &Type::TotalT))
+	});
+	if let Err(e) = settled {
+		panic!("Effects not matched! {}", e)
+	}
+	let program = infer.elaborate(&ast);
+	let schemes = infer.schemes.iter().map(|(name, t)| (name.to_string(), principal(&infer.resolve(t)))).collect();
+	let holes = infer.holes.iter().map(|hole| infer.resolve_hole(hole)).collect();
+	let root = infer.frames.pop().unwrap().pop().unwrap();
+	let tree = infer.resolve_tree(&root);
+	Checked { result: principal(&infer.resolve(&result)), schemes, holes, tree, program }
+}
+
+/* wrap every node of `copy` whose twin in `original` crossed a dynT boundary
+ * in its casts, innermost first; the number of nodes wrapped
+ */
+fn wrap_casts(original: &AST, copy: &mut AST, casts: &HashMap<*const AST, Vec<(Type, Type, String, String)>>) -> usize {
+	let mut wrapped = 0;
+	for ((_bound, twin), child) in original.scoped_children().into_iter().zip(copy.children_mut()) {
+		wrapped += wrap_casts(twin, child, casts);
+	}
+	if let Some(node_casts) = casts.get(&(original as *const AST)) {
+		for (from, to, positive, negative) in node_casts.iter() {
+			let inner = std::mem::replace(copy, AST::None);
+			*copy = AST::AcastC(Box::new(inner), from.clone(), to.clone(), positive.to_string(), negative.to_string());
+		}
+		wrapped += 1;
+	}
+	wrapped
+}
+
+/* the typed tree: every sub-expression with the type it was given */
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedNode {
+	pub label: String,
+	pub ty: Type,
+	pub children: Vec<TypedNode>,
+}
+
+impl TypedNode {
+	/* every type in the tree, parents before children */
+	pub fn types(&self) -> Vec<&Type> {
+		let mut types = vec![&self.ty];
+		for child in self.children.iter() {
+			types.extend(child.types());
+		}
+		types
+	}
+
+	pub fn map_types(&self, f: &mut dyn FnMut(&Type) -> Type) -> TypedNode {
+		TypedNode {
+			label: self.label.to_string(),
+			ty: f(&self.ty),
+			children: self.children.iter().map(|child| child.map_types(f)).collect(),
+		}
+	}
+
+	fn fmt_indented(&self, f: &mut fmt::Formatter, depth: usize) -> fmt::Result {
+		writeln!(f, "{}{} : {}", "  ".repeat(depth), self.label, self.ty)?;
+		for child in self.children.iter() {
+			child.fmt_indented(f, depth + 1)?;
+		}
+		Ok(())
+	}
+}
+
+/* one node per line, children indented under their parent */
+impl fmt::Display for TypedNode {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		self.fmt_indented(f, 0)
+	}
+}
+
+/* holeC("name") or ?name: accepted as any type, and reported with the type
+ * the rest of the program expects there and the bindings in scope
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hole {
+	pub name: String,
+	pub expected: Type,
+	pub scope: Vec<(String, Type)>, /* sorted by name */
+}
+
+impl fmt::Display for Hole {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "?{} : {}", self.name, self.expected)?;
+		for (var, t) in self.scope.iter() {
+			write!(f, "\n    {} : {}", var, t)?;
+		}
+		Ok(())
+	}
+}
+
+/* --------------- Type Environment ---------------
+ * The bindings in scope while checking. Entering a binder pushes onto the
+ * name's own stack and leaving it pops, so extending the environment is O(1)
+ * instead of a clone of the whole map; the newest binding of a name shadows
+ * the older ones, as the cloned HashMap's insert used to.
+ */
+#[derive(Debug, Clone, Default)]
+pub struct Env<'a> {
+	scopes: HashMap<String, Vec<(Type, usize)>>, /* with the index in `order` */
+	order: Vec<String>, /* the names in the order they were bound */
+	binders: Vec<Binder<'a>>, /* what bound each name in `order` */
+	retired: HashMap<String, Binder<'a>>, /* the last binder of each name that went out of scope */
+}
+
+/* what bound a name, only described when a message needs it */
+#[derive(Debug, Clone, Copy)]
+pub enum Binder<'a> {
+	Initial,
+	Node(&'a AST),
+	Arm(&'a String), /* the matchC arm of a variant */
+}
+
+impl fmt::Display for Binder<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Binder::Initial => write!(f, "the initial type environment"),
+			Binder::Node(ast) => write!(f, "{}", ast_label(ast)),
+			Binder::Arm(variant) => write!(f, "the matchC arm of {}", variant),
+		}
+	}
+}
+
+impl<'a> Env<'a> {
+	pub fn new() -> Env<'a> {
+		Env { scopes: HashMap::new(), order: Vec::new(), binders: Vec::new(), retired: HashMap::new() }
+	}
+
+	/* bind `name`; `binder` says where, e.g. fdC("x"), for error messages */
+	pub fn extend(&mut self, name: String, t: Type, binder: Binder<'a>) {
+		self.scopes.entry(name.to_string()).or_default().push((t, self.order.len()));
+		self.binders.push(binder);
+		self.order.push(name);
+	}
+
+	/* forget the newest binding */
+	pub fn pop(&mut self) {
+		if let Some(name) = self.order.pop() {
+			let shadowed = self.scopes.get_mut(&name).unwrap();
+			shadowed.pop();
+			if shadowed.is_empty() {
+				self.scopes.remove(&name);
+			}
+			let binder = self.binders.pop().unwrap();
+			self.retired.insert(name, binder);
+		}
+	}
+
+	/* the number of bindings, to truncate back to when leaving a scope */
+	pub fn len(&self) -> usize {
+		self.order.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.order.is_empty()
+	}
+
+	pub fn truncate(&mut self, len: usize) {
+		while self.order.len() > len {
+			self.pop();
+		}
+	}
+
+	pub fn get(&self, name: &str) -> Option<&Type> {
+		self.scopes.get(name).and_then(|shadowed| shadowed.last()).map(|(t, _binder)| t)
+	}
+
+	/* the expression that bound the visible `name` */
+	pub fn binder(&self, name: &str) -> Option<Binder<'a>> {
+		self.scopes.get(name).and_then(|shadowed| shadowed.last()).map(|(_t, index)| self.binders[*index])
+	}
+
+	pub fn lookup(&self, id: &str) -> Type {
+		match self.get(id) {
+			Some(t) => t.clone(),
+			_ => panic!("Unexpected identifier: {}{}", id, self.suggest(id)),
+		}
+	}
+
+	/* the names in scope close to `id` by edit distance, and the binder of
+	 * the closest name bound anywhere so far, in scope or not
+	 */
+	pub fn suggest(&self, id: &str) -> String {
+		let limit = std::cmp::max(1, id.chars().count().div_ceil(2));
+		let mut close: Vec<(usize, &String)> = self.scopes.keys().map(|name| (edit_distance(id, name), name)).filter(|(d, _name)| *d <= limit).collect();
+		close.sort();
+		let mut message = String::new();
+		if !close.is_empty() {
+			let names: Vec<&str> = close.iter().take(3).map(|(_d, name)| name.as_str()).collect();
+			message.push_str(&format!(". Did you mean {}?", names.join(" or ")));
+		}
+		/* the latest binder wins a tie, and one in scope wins over one that is not */
+		let bound = self.order.iter().zip(self.binders.iter()).rev().chain(self.retired.iter());
+		let nearest = bound.map(|(name, binder)| (edit_distance(id, name), name, binder)).filter(|(d, _name, _binder)| *d <= limit).min_by_key(|(d, _name, _binder)| *d);
+		if let Some((_d, name, binder)) = nearest {
+			if close.is_empty() {
+				message.push('.');
+			}
+			let in_scope = if self.get(name).is_some() { "" } else { ", which is not in scope here" };
+			message.push_str(&format!(" The nearest binder is {} from {}{}.", name, binder, in_scope));
+		}
+		message
+	}
+
+	/* the visible bindings, without the shadowed ones */
+	pub fn bindings(&self) -> impl Iterator<Item = (&String, &Type)> {
+		self.scopes.iter().map(|(name, shadowed)| (name, &shadowed.last().unwrap().0))
+	}
+
+	pub fn types(&self) -> impl Iterator<Item = &Type> {
+		self.bindings().map(|(_name, t)| t)
+	}
+}
+
+impl From<&HashMap<String, Type>> for Env<'_> {
+	fn from(tnv: &HashMap<String, Type>) -> Self {
+		let mut env = Env::new();
+		for (name, t) in tnv.iter() {
+			env.extend(name.to_string(), t.clone(), Binder::Initial);
+		}
+		env
+	}
+}
+
+/* Hindley-Milner inference state: the substitution found so far,
+ * the counter used to create fresh type variables and the schemes
+ * given to let-bound names
+ */
+pub struct Infer<'a> {
+	subst: HashMap<usize, Type>,
+	next_var: usize,
+	pub schemes: Vec<(String, Type)>,
+	tyvars: Vec<String>, /* type variables bound by the enclosing tyLamC's */
+	datatypes: HashMap<String, Vec<(String, Vec<Type>)>>, /* variants of each datatypeC */
+	top_level: bool, /* no expression has been entered yet, only datatypeC's */
+	eq_vars: Vec<usize>, /* type variables that may only become equality types */
+	holes: Vec<Hole>,
+	frames: Vec<Vec<TypedNode>>, /* the typed children of each node being checked */
+	casts: Vec<(&'a AST, Type, Type, String, String)>, /* node, from, to and the two blame labels of each dynT boundary */
+	nodes: Vec<&'a AST>, /* the nodes being checked, innermost last */
+	calls: Vec<Vec<(Type, String)>>, /* the effect and callee of each call in the function bodies being checked */
+	effect_bounds: Vec<(Type, Type)>, /* unknown effects, each at most the other */
+}
+
+impl Default for Infer<'_> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<'a> Infer<'a> {
+	pub fn new() -> Infer<'a> {
+		Infer {
+			subst: HashMap::new(),
+			next_var: 0,
+			schemes: Vec::new(),
+			tyvars: Vec::new(),
+			datatypes: HashMap::new(),
+			top_level: true,
+			eq_vars: Vec::new(),
+			holes: Vec::new(),
+			frames: vec![Vec::new()],
+			casts: Vec::new(),
+			nodes: Vec::new(),
+			calls: vec![Vec::new()],
+			effect_bounds: Vec::new(),
+		}
+	}
+
+	pub fn fresh(&mut self) -> Type {
+		self.next_var += 1;
+		Type::VarT(self.next_var - 1)
+	}
+
+	/* replace `_` annotations with fresh type variables, and make sure
+	 * every type variable of the annotation is bound by a tyLamC
+	 */
+	pub fn annotation(&mut self, t: Type) -> Type {
+		for a in free_tyvars(&t) {
+			if !self.tyvars.contains(&a) {
+				panic!("Unbound type variable: {}", a)
+			}
+		}
+		for name in named_types(&t) {
+			if !self.datatypes.contains_key(&name) {
+				panic!("Unknown datatype: {}", name)
+			}
+		}
+		self.fill_blanks(t)
+	}
+
+	/* the datatype a variant belongs to, and the types of its fields */
+	fn variant(&self, variant: &str) -> (String, Vec<Type>) {
+		for (name, variants) in self.datatypes.iter() {
+			if let Some((_variant, fields)) = variants.iter().find(|(v, _fields)| v == variant) {
+				return (name.to_string(), fields.clone());
+			}
+		}
+		panic!("Unknown variant: {}", variant)
+	}
+
+	fn fill_blanks(&mut self, t: Type) -> Type {
+		match t {
+			Type::BlankT => self.fresh(),
+			_ => t.map_children(&mut |child| self.fill_blanks(child.clone())),
+		}
+	}
+
+	/* apply the substitution all the way down */
+	pub fn resolve(&self, t: &Type) -> Type {
+		apply_subst(&self.subst, t)
+	}
+
+	pub fn unify(&mut self, first: &Type, second: &Type) -> Result<(), String> {
+		let first = self.resolve(first);
+		let second = self.resolve(second);
+		match (&first, &second) {
+			(Type::VarT(a), Type::VarT(b)) if a == b => Ok(()),
+			(Type::VarT(v), t) | (t, Type::VarT(v)) => {
+				if free_vars(t).contains(v) {
+					let shown = principal(&Type::TupleT(vec![first.clone(), second.clone()]));
+					match shown {
+						Type::TupleT(pair) => Err(format!("infinite type: {} occurs in {}", pair[0], pair[1])),
+						_ => Err("infinite type".to_string()),
+					}
+				} else {
+					self.subst.insert(*v, t.clone());
+					if self.eq_vars.contains(v) {
+						if let Some(bad) = self.non_equality(t, &mut Vec::new()) {
+							let reason = match bad {
+								Type::FunT(_, _, _) => "functions cannot be compared",
+								Type::TvarT(_) => "a type variable may stand for a function",
+								Type::DynT => "dyn may stand for a function",
+								_ => "polymorphic values cannot be compared",
+							};
+							let t = principal(&self.resolve(t));
+							let bad = principal(&bad);
+							if t == bad {
+								return Err(format!("{} is not an equality type, {}", t, reason))
+							}
+							return Err(format!("{} is not an equality type: it contains {}, and {}", t, bad, reason))
+						}
+					}
+					/* an effect variable may have bounds to check again */
+					if self.effect_bounds.iter().any(|(sub, sup)| *sub == Type::VarT(*v) || *sup == Type::VarT(*v)) {
+						self.settle_effects()?;
+					}
+					Ok(())
+				}
+			},
+			/* consistency: dynT fits anything, checked by a cast at run time */
+			(Type::DynT, _) | (_, Type::DynT) => Ok(()),
+			(Type::TotalT, Type::DivT) | (Type::DivT, Type::TotalT) => Err("a function that may diverge is used where a total one is expected".to_string()),
+			(Type::ForallT(a1, b1), Type::ForallT(a2, b2)) | (Type::MuT(a1, b1), Type::MuT(a2, b2)) => {
+				/* rename both bound variables to the same fresh name */
+				let mut avoid = free_tyvars(b1);
+				avoid.extend(free_tyvars(b2));
+				avoid.extend(self.tyvars.iter().cloned());
+				let fresh = Type::TvarT(fresh_tyvar(a1, &avoid));
+				self.unify(&type_subst(b1, a1, &fresh), &type_subst(b2, a2, &fresh))
+			},
+			_ => {
+				if first.same_shape(&second) {
+					for (a, b) in first.children().into_iter().zip(second.children()) {
+						self.unify(a, b)?;
+					}
+					Ok(())
+				} else {
+					let shown = principal(&Type::TupleT(vec![first.clone(), second.clone()]));
+					match shown {
+						Type::TupleT(pair) => Err(format!("cannot match {} with {}", pair[0], pair[1])),
+						_ => Err("type mismatch".to_string()),
+					}
+				}
+			},
+		}
+	}
+
+	/* equality types are built from num, bool, str and unit with tuples, sums,
+	 * lists, records, datatypes and muT's; a ref is compared by identity, so
+	 * any ref is one, and a dyn may hold a function, so it is not. Returns the part that is not, e.g. a function type, and
+	 * marks the type variables inside as equality type variables.
+	 */
+	fn non_equality(&mut self, t: &Type, seen: &mut Vec<String>) -> Option<Type> {
+		match self.resolve(t) {
+			Type::VarT(v) => {
+				if !self.eq_vars.contains(&v) {
+					self.eq_vars.push(v);
+				}
+				None
+			},
+			Type::RefT(_) => None,
+			Type::NamedT(name) => {
+				if seen.contains(&name) {
+					return None
+				}
+				seen.push(name.to_string());
+				let fields: Vec<Type> = self.datatypes.get(&name).into_iter().flatten().flat_map(|(_variant, fields)| fields.clone()).collect();
+				fields.iter().find_map(|field| self.non_equality(field, seen))
+			},
+			Type::MuT(a, body) => self.non_equality(&type_subst(&body, &a, &Type::UnitT), seen),
+			bad @ (Type::FunT(_, _, _) | Type::SchemeT(_, _) | Type::ForallT(_, _) | Type::TvarT(_) | Type::DynT) => Some(bad),
+			other => {
+				let children: Vec<Type> = other.children().into_iter().cloned().collect();
+				children.iter().find_map(|child| self.non_equality(child, seen))
+			},
+		}
+	}
+
+	/* sub <: sup, with width and depth subtyping on records; function
+	 * parameters are contravariant, immutable containers are covariant,
+	 * and everything else (including type variables) is unified
+	 */
+	pub fn subtype(&mut self, sub: &Type, sup: &Type) -> Result<(), String> {
+		let sub = self.resolve(sub);
+		let sup = self.resolve(sup);
+		match (&sub, &sup) {
+			(Type::RecordT(sub_fields), Type::RecordT(sup_fields)) => {
+				for (name, sup_field) in sup_fields.iter() {
+					match sub_fields.iter().find(|(field, _t)| field == name) {
+						Some((_field, sub_field)) => self.subtype(sub_field, sup_field)?,
+						None => return Err(format!("{} has no field {}", principal(&sub), name)),
+					}
+				}
+				Ok(())
+			},
+			(Type::FunT(a1, r1, e1), Type::FunT(a2, r2, e2)) => {
+				self.subtype(a2, a1)?;
+				self.subtype(r1, r2)?;
+				self.subeffect(e1, e2)
+			},
+			(Type::TupleT(_), Type::TupleT(_)) | (Type::SumT(_, _), Type::SumT(_, _)) | (Type::ListT(_), Type::ListT(_))
+				if sub.same_shape(&sup) => {
+				for (a, b) in sub.children().into_iter().zip(sup.children()) {
+					self.subtype(a, b)?;
+				}
+				Ok(())
+			},
+			_ => self.unify(&sub, &sup),
+		}
+	}
+
+	/* a total function can be used where one that may diverge is expected.
+	 * An unknown effect below divT or above totalT is not bounded by it; a
+	 * bound between two unknown effects is kept until one of them is solved
+	 */
+	pub fn subeffect(&mut self, sub: &Type, sup: &Type) -> Result<(), String> {
+		self.bound_effect(sub, sup)?;
+		self.settle_effects()
+	}
+
+	fn bound_effect(&mut self, sub: &Type, sup: &Type) -> Result<(), String> {
+		match (self.resolve(sub), self.resolve(sup)) {
+			(Type::TotalT, _) | (_, Type::DivT) => Ok(()),
+			(Type::VarT(a), Type::VarT(b)) if a == b => Ok(()),
+			(sub @ Type::VarT(_), sup @ Type::VarT(_)) => {
+				self.effect_bounds.push((sub, sup));
+				Ok(())
+			},
+			(sub, sup) => self.unify(&sub, &sup),
+		}
+	}
+
+	/* check the kept bounds again until no more of their variables get solved */
+	pub fn settle_effects(&mut self) -> Result<(), String> {
+		loop {
+			let solved = self.subst.len();
+			for (sub, sup) in std::mem::take(&mut self.effect_bounds) {
+				self.bound_effect(&sub, &sup)?;
+			}
+			if self.subst.len() == solved {
+				return Ok(())
+			}
+		}
+	}
+
+	/* the effect of calling a function whose body made `calls`: it may
+	 * diverge if any callee may, and is bounded by the unknown callee
+	 * effects. A declared total function requires every callee to be.
+	 */
+	fn latent_effect(&mut self, calls: Vec<(Type, String)>, total: bool, name: &str) -> Result<Type, String> {
+		let diverges = |callee: &str| format!("fdC(\"{}\") is declared total, but it calls {}, which may diverge", name, callee);
+		let mut unknown = Vec::new();
+		for (callee_effect, callee) in calls {
+			match self.resolve(&callee_effect) {
+				Type::DivT if total => return Err(diverges(&callee)),
+				Type::DivT => return Ok(Type::DivT),
+				/* a bound kept on the callee effect may already force it to diverge */
+				Type::VarT(_) if total => self.subeffect(&callee_effect, &Type::TotalT).map_err(|_| diverges(&callee))?,
+				Type::VarT(_) => unknown.push(callee_effect),
+				_ => {},
+			}
+		}
+		if unknown.is_empty() {
+			return Ok(Type::TotalT)
+		}
+		let effect = self.fresh();
+		for callee_effect in unknown {
+			self.subeffect(&callee_effect, &effect)?;
+		}
+		Ok(effect)
+	}
+
+	/* the least type both branches can be used at: records keep the
+	 * fields they share, anything else must be the same type
+	 */
+	pub fn join(&mut self, first: &Type, second: &Type) -> Result<Type, String> {
+		let first = self.resolve(first);
+		let second = self.resolve(second);
+		match (&first, &second) {
+			(Type::RecordT(first_fields), Type::RecordT(second_fields)) => {
+				let mut fields = Vec::new();
+				for (name, t1) in first_fields.iter() {
+					if let Some((_field, t2)) = second_fields.iter().find(|(field, _t)| field == name) {
+						/* a field that cannot be joined is dropped, and so is
+						 * anything the attempt solved before it failed
+						 */
+						let saved = (self.subst.clone(), self.eq_vars.clone(), self.effect_bounds.clone());
+						match self.join(t1, t2) {
+							Ok(t) => fields.push((name.to_string(), t)),
+							Err(_) => (self.subst, self.eq_vars, self.effect_bounds) = saved,
+						}
+					}
+				}
+				Ok(Type::RecordT(fields))
+			},
+			(Type::DynT, _) | (_, Type::DynT) => Ok(Type::DynT),
+			_ => {
+				self.unify(&first, &second)?;
+				Ok(first)
+			},
+		}
+	}
+
+	/* quantify the variables that are not free in the environment */
+	pub fn generalize(&self, t: &Type, tnv: &Env) -> Type {
+		let t = self.resolve(t);
+		let mut env_vars = Vec::new();
+		for ty in tnv.types() {
+			env_vars.extend(free_vars(&self.resolve(ty)));
+		}
+		/* an effect bounded by one in the environment is not free to choose either */
+		let mut grown = true;
+		while grown {
+			grown = false;
+			for (sub, sup) in self.effect_bounds.iter() {
+				let (sub, sup) = (free_vars(&self.resolve(sub)), free_vars(&self.resolve(sup)));
+				if sub.iter().chain(sup.iter()).any(|v| env_vars.contains(v)) {
+					for v in sub.into_iter().chain(sup) {
+						if !env_vars.contains(&v) {
+							env_vars.push(v);
+							grown = true;
+						}
+					}
+				}
+			}
+		}
+		let vars: Vec<usize> = free_vars(&t).into_iter().filter(|v| !env_vars.contains(v)).collect();
+		if vars.is_empty() {
+			t
+		} else {
+			Type::SchemeT(vars, Box::new(t))
+		}
+	}
+
+	/* the hole with its types resolved, numbered together so that a type
+	 * variable shared by the goal and a binding prints the same in both
+	 */
+	pub fn resolve_hole(&self, hole: &Hole) -> Hole {
+		let mut all = vec![self.resolve(&hole.expected)];
+		all.extend(hole.scope.iter().map(|(_var, t)| self.resolve(t)));
+		match principal(&Type::TupleT(all)) {
+			Type::TupleT(mut all) => {
+				let scope = all.split_off(1);
+				Hole {
+					name: hole.name.to_string(),
+					expected: all.remove(0),
+					scope: hole.scope.iter().map(|(var, _t)| var.to_string()).zip(scope).collect(),
+				}
+			},
+			_ => hole.clone(),
+		}
+	}
+
+	/* the tree with its types resolved, numbered across the whole tree */
+	pub fn resolve_tree(&self, root: &TypedNode) -> TypedNode {
+		let resolved = root.map_types(&mut |t| self.resolve(t));
+		let mut renaming = HashMap::new();
+		for t in resolved.types() {
+			for v in free_vars(t) {
+				if !renaming.contains_key(&v) {
+					let next = renaming.len();
+					renaming.insert(v, Type::VarT(next));
+				}
+			}
+		}
+		resolved.map_types(&mut |t| rename_vars(t, &renaming))
+	}
+
+	/* give every quantified variable of a scheme a fresh name */
+	pub fn instantiate(&mut self, t: &Type) -> Type {
+		match t {
+			Type::SchemeT(vars, body) => {
+				let mut renaming = HashMap::new();
+				for v in vars.iter() {
+					let fresh = self.fresh();
+					if self.eq_vars.contains(v) {
+						self.eq_vars.extend(free_vars(&fresh));
+					}
+					renaming.insert(*v, fresh);
+				}
+				/* every use gets its own copy of the bounds on the effects */
+				let bounds: Vec<(Type, Type)> = self.effect_bounds.iter()
+					.filter(|(sub, sup)| free_vars(sub).iter().chain(free_vars(sup).iter()).any(|v| renaming.contains_key(v)))
+					.map(|(sub, sup)| (rename_vars(sub, &renaming), rename_vars(sup, &renaming)))
+					.collect();
+				self.effect_bounds.extend(bounds);
+				rename_vars(body, &renaming)
+			},
+			_ => t.clone(),
+		}
+	}
+
+	/* inlC / inrC: the annotation gives the whole sum type */
+	fn inject(&mut self, expr: &'a AST, t: Type, left: bool, tnv: &mut Env<'a>) -> Type {
+		let sum_type = match self.annotation(t) {
+			Type::SumT(l, r) => Type::SumT(l, r),
+			Type::VarT(_) => Type::SumT(Box::new(self.fresh()), Box::new(self.fresh())),
+			other => panic!("Injection must be annotated with a sum type, not {}!", principal(&other)),
+		};
+		let expr_type = self.infer(expr, tnv);
+		if let Type::SumT(l, r) = &sum_type {
+			let side = if left { l } else { r };
+			if let Err(e) = self.fit(expr, &expr_type, side, tnv) {
+				panic!("Injection not matched! {}", e)
+			}
+		}
+		sum_type
+	}
+
+	/* the element type of an expression that must be a box, whose
+	 * functions may diverge (see diverging)
+	 */
+	fn ref_elem(&mut self, expr: &'a AST, tnv: &mut Env<'a>) -> Type {
+		let ref_type = self.infer(expr, tnv);
+		if self.resolve(&ref_type) == Type::DynT {
+			self.record_cast(expr, &Type::DynT, &Type::RefT(Box::new(Type::DynT)), tnv);
+			return Type::DynT
+		}
+		let elem_type = self.fresh();
+		if let Err(e) = self.unify(&ref_type, &Type::RefT(Box::new(elem_type.clone()))) {
+			panic!("Not a box! {}", e)
+		}
+		diverging(&self.resolve(&elem_type))
+	}
+
+	/* the element type of an expression that must be a list */
+	fn list_elem(&mut self, list: &'a AST, tnv: &mut Env<'a>) -> Type {
+		let list_type = self.infer(list, tnv);
+		if self.resolve(&list_type) == Type::DynT {
+			self.record_cast(list, &Type::DynT, &Type::ListT(Box::new(Type::DynT)), tnv);
+			return Type::DynT
+		}
+		let elem_type = self.fresh();
+		if let Err(e) = self.unify(&list_type, &Type::ListT(Box::new(elem_type.clone()))) {
+			panic!("Not a list! {}", e)
+		}
+		elem_type
+	}
+
+	/* unify the type inferred for `ast` with the one its context needs;
+	 * where one side is dynT they are only consistent, and a cast checks it
+	 */
+	fn fit(&mut self, ast: &'a AST, inferred: &Type, expected: &Type, tnv: &Env<'a>) -> Result<(), String> {
+		self.unify(inferred, expected)?;
+		self.dyn_effects(inferred, expected)?;
+		self.record_cast(ast, inferred, expected, tnv);
+		Ok(())
+	}
+
+	/* a cast cannot tell whether a function may diverge, so every function
+	 * a value of type `from` turns into where `to` has it out of a dynT does
+	 */
+	fn dyn_effects(&mut self, from: &Type, to: &Type) -> Result<(), String> {
+		match (self.resolve(from), self.resolve(to)) {
+			(Type::DynT, to) => self.diverge(&to),
+			(Type::FunT(from_param, from_ret, _), Type::FunT(to_param, to_ret, _)) => {
+				self.dyn_effects(&to_param, &from_param)?;
+				self.dyn_effects(&from_ret, &to_ret)
+			},
+			(Type::RecordT(from_fields), Type::RecordT(to_fields)) => {
+				for (name, to_field) in to_fields.iter() {
+					if let Some((_name, from_field)) = from_fields.iter().find(|(field, _t)| field == name) {
+						self.dyn_effects(from_field, to_field)?;
+					}
+				}
+				Ok(())
+			},
+			/* a box is not cast, and what is read from it may diverge anyway */
+			(Type::RefT(_), _) => Ok(()),
+			(from, to) if std::mem::discriminant(&from) == std::mem::discriminant(&to) && from.children().len() == to.children().len() => {
+				for (from_child, to_child) in from.children().into_iter().zip(to.children()) {
+					self.dyn_effects(from_child, to_child)?;
+				}
+				Ok(())
+			},
+			_ => Ok(()),
+		}
+	}
+
+	/* every function `t` gives out may diverge; its parameters are given in */
+	fn diverge(&mut self, t: &Type) -> Result<(), String> {
+		match self.resolve(t) {
+			Type::FunT(_param, ret, effect) => {
+				self.subeffect(&Type::DivT, &effect)?;
+				self.diverge(&ret)
+			},
+			Type::RefT(_) => Ok(()),
+			t => {
+				for child in t.children() {
+					self.diverge(child)?;
+				}
+				Ok(())
+			},
+		}
+	}
+
+	/* a boundary between dynT and a static type is checked by a cast at run
+	 * time; whether one is needed is only known once the variables are solved.
+	 * The positive label, blamed when the value does not fit, names the binder
+	 * when the node is an idC; the negative one, blamed when a function it
+	 * becomes is given a wrong argument, names the context that uses it.
+	 */
+	fn record_cast(&mut self, ast: &'a AST, from: &Type, to: &Type, tnv: &Env<'a>) {
+		let from = self.resolve(from);
+		let to = self.resolve(to);
+		let undecided = has_dyn(&from) || has_dyn(&to) || !free_vars(&from).is_empty() || !free_vars(&to).is_empty();
+		if from == to || !undecided {
+			return
+		}
+		let positive = match (ast, ast_binder(ast, tnv)) {
+			(AST::AidC(id), Some(binder)) => format!("{} bound by {}", id, binder),
+			_ => ast_label(ast),
+		};
+		/* a node checked in place is on the stack itself */
+		let parents = match self.nodes.last() {
+			Some(last) if std::ptr::eq(*last, ast) => &self.nodes[..self.nodes.len() - 1],
+			_ => &self.nodes[..],
+		};
+		let negative = match parents.last() {
+			/* an argument is used by the parameter it is passed to */
+			Some(AST::AappC(fun, arg)) if std::ptr::eq(&**arg, ast) => match (&**fun, ast_binder(fun, tnv)) {
+				(AST::AfdC(param, _, _, _, _), _) => format!("{} bound by {}", param, ast_label(fun)),
+				(AST::AidC(id), Some(binder)) => format!("the parameter of {} bound by {}", id, binder),
+				(fun, _) => format!("the parameter of {}", ast_label(fun)),
+			},
+			Some(parent) => ast_label(parent),
+			None => "the program".to_string(),
+		};
+		self.casts.push((ast, from, to, positive, negative));
+	}
+
+	/* a copy of `ast`, which must be the program this inferred, with a castC
+	 * around every node that still needs one with the final substitution
+	 */
+	pub fn elaborate(&self, ast: &'a AST) -> AST {
+		let mut casts: HashMap<*const AST, Vec<(Type, Type, String, String)>> = HashMap::new();
+		for (node, from, to, positive, negative) in self.casts.iter() {
+			let from = self.resolve(from);
+			let to = self.resolve(to);
+			if from != to && (has_dyn(&from) || has_dyn(&to)) {
+				casts.entry(*node as *const AST).or_default().push((from, to, positive.to_string(), negative.to_string()));
+			}
+		}
+		let mut program = ast.clone();
+		if wrap_casts(ast, &mut program, &casts) != casts.len() {
+			panic!("Casts were recorded for another program than the one elaborated!")
+		}
+		program
+	}
+
+	/* "expected X, but inferred Y", numbered together */
+	fn mismatch(&self, expected: &Type, inferred: &Type, e: String) -> String {
+		match principal(&Type::TupleT(vec![self.resolve(expected), self.resolve(inferred)])) {
+			Type::TupleT(both) => format!("expected {}, but inferred {}: {}", both[0], both[1], e),
+			_ => e,
+		}
+	}
+
+	/* checking mode: the type the context expects flows into the expression,
+	 * so an fdC there can leave out its annotations; anything else is inferred
+	 * and must fit the expected type by subtyping or by instantiating a forallT
+	 */
+	pub fn check(&mut self, ast: &'a AST, expected: &Type, tnv: &mut Env<'a>) -> Result<(), String> {
+		let expected = self.resolve(expected);
+		let checked_here = matches!((&ast, &expected), (AST::AfdC(_, _, _, _, _), Type::FunT(_, _, _)) | (AST::AifC(_, _, _), _))
+			|| matches!(&ast, AST::AseqC(exprs) if !exprs.is_empty());
+		if !checked_here {
+			/* inferred, and recorded in the typed tree by infer */
+			return self.check_node(ast, &expected, tnv)
+		}
+		let label = ast_label(ast);
+		self.frames.push(Vec::new());
+		self.nodes.push(ast);
+		let result = self.check_node(ast, &expected, tnv);
+		self.nodes.pop();
+		let children = self.frames.pop().unwrap();
+		self.frames.last_mut().unwrap().push(TypedNode { label, ty: expected, children });
+		result
+	}
+
+	fn check_node(&mut self, ast: &'a AST, expected: &Type, tnv: &mut Env<'a>) -> Result<(), String> {
+		match (ast, self.resolve(expected)) {
+			(AST::AfdC(name, inpt, ret, declared, body), Type::FunT(param_type, ret_type, effect)) => {
+				let expected_fun = Type::FunT(param_type.clone(), ret_type.clone(), effect.clone());
+				/* an annotation must accept every argument the context may pass */
+				let inpt = if **inpt == Type::BlankT {
+					*param_type
+				} else {
+					let inpt = self.annotation((**inpt).clone());
+					if let Err(e) = self.subtype(&param_type, &inpt) {
+						return Err(format!("expected a parameter of type {}, but it is annotated {}: {}", principal(&self.resolve(&param_type)), principal(&inpt), e))
+					}
+					inpt
+				};
+				let ret = if **ret == Type::BlankT {
+					*ret_type
+				} else {
+					let ret = self.annotation((**ret).clone());
+					if let Err(e) = self.subtype(&ret, &ret_type) {
+						return Err(format!("expected a result of type {}, but it is annotated {}: {}", principal(&self.resolve(&ret_type)), principal(&ret), e))
+					}
+					ret
+				};
+				self.top_level = false;
+				/* annotations consistent with a dynT in the expected type are checked by a cast */
+				let annotated = Type::FunT(Box::new(inpt.clone()), Box::new(ret.clone()), effect.clone());
+				self.dyn_effects(&annotated, &expected_fun)?;
+				self.record_cast(ast, &annotated, &expected_fun, tnv);
+				tnv.extend(name.to_string(), inpt, Binder::Node(ast));
+				self.calls.push(Vec::new());
+				let result = self.check(body, &ret, tnv).map_err(|e| format!("in the body of fdC(\"{}\"), {}", name, e));
+				tnv.pop();
+				let calls = self.calls.pop().unwrap();
+				result?;
+				let total = **declared == Type::TotalT || self.resolve(&effect) == Type::TotalT;
+				let latent = self.latent_effect(calls, total, name)?;
+				/* a function declared divT is taken at its word */
+				let latent = if **declared == Type::DivT { Type::DivT } else { latent };
+				self.subeffect(&latent, &effect)
+			},
+			(AST::AifC(ifc, thenc, elsec), expected) => {
+				self.top_level = false;
+				if let Err(e) = self.check(ifc, &Type::BoolT, tnv) {
+					panic!("Invalid control flow! {}", e)
+				}
+				self.check(thenc, &expected, tnv)?;
+				self.check(elsec, &expected, tnv)
+			},
+			(AST::AseqC(exprs), expected) if !exprs.is_empty() => {
+				self.top_level = false;
+				let (last, exprs) = exprs.split_last().unwrap();
+				for expr in exprs {
+					self.infer(expr, tnv);
+				}
+				self.check(last, &expected, tnv)
+			},
+			(ast, expected) => {
+				let mut inferred = self.infer(ast, tnv);
+				/* a type variable may still become a forallT, so it keeps the polymorphism */
+				if !matches!(expected, Type::ForallT(_, _) | Type::VarT(_)) {
+					while let Type::ForallT(a, body) = self.resolve(&inferred) {
+						let fresh = self.fresh();
+						inferred = type_subst(&body, &a, &fresh);
+					}
+				}
+				self.subtype(&inferred, &expected).map_err(|e| self.mismatch(&expected, &inferred, e))?;
+				self.dyn_effects(&inferred, &expected).map_err(|e| self.mismatch(&expected, &inferred, e))?;
+				self.record_cast(ast, &inferred, &expected, tnv);
+				Ok(())
+			},
+		}
+	}
+
+	/* inference mode: the type is synthesized from the expression alone */
+	pub fn infer(&mut self, ast: &'a AST, tnv: &mut Env<'a>) -> Type {
+		let label = ast_label(ast);
+		self.frames.push(Vec::new());
+		self.nodes.push(ast);
+		let ty = self.infer_node(ast, tnv);
+		self.nodes.pop();
+		let children = self.frames.pop().unwrap();
+		self.frames.last_mut().unwrap().push(TypedNode { label, ty: ty.clone(), children });
+		ty
+	}
+
+	fn infer_node(&mut self, ast: &'a AST, tnv: &mut Env<'a>) -> Type {
+		let top_level = self.top_level;
+		if !matches!(ast, AST::AdatatypeC(_, _, _)) {
+			self.top_level = false;
+		}
+		match ast {
+			AST::AnumC(_i) => {Type::NumT},
+			AST::AprimC(name, args) => {
+				let prim = primitive(name).unwrap();
+				let mut sig = self.instantiate(&prim.signature());
+				if prim.equality {
+					self.eq_vars.extend(free_vars(&sig));
+				}
+				for arg in args {
+					let (param_type, rest) = match sig {
+						Type::FunT(param_type, rest, _) => (*param_type, *rest),
+						_ => panic!("{} takes {} params.", name, prim.arity()),
+					};
+					if let Err(e) = self.check(arg, &param_type, tnv) {
+						panic!("{} {}", prim.error, e)
+					}
+					sig = rest;
+				}
+				sig
+			},
+			AST::AtrueC(_b) => Type::BoolT,
+			AST::AfalseC(_b) => Type::BoolT,
+			AST::AifC(ifc, thenc, elsec) => {
+				if let Err(e) = self.check(ifc, &Type::BoolT, tnv) {
+					panic!("Invalid control flow! {}", e)
+				}
+				let then_type = self.infer(thenc, tnv);
+				let else_type = self.infer(elsec, tnv);
+				match self.join(&then_type, &else_type) {
+					Ok(Type::DynT) => {
+						/* a static branch flows into dynT */
+						self.record_cast(thenc, &then_type, &Type::DynT, tnv);
+						self.record_cast(elsec, &else_type, &Type::DynT, tnv);
+						Type::DynT
+					},
+					Ok(t) => t,
+					Err(e) => panic!("If-else not matched! {}", e),
+				}
+			},
+			AST::AidC(s) => {
+				let t = tnv.lookup(s);
+				self.instantiate(&t)
+			},
+			AST::AfdC(name, inpt, ret, declared, body) => {
+				let inpt = self.annotation((**inpt).clone());
+				let ret = self.annotation((**ret).clone());
+				tnv.extend(name.to_string(), inpt.clone(), Binder::Node(ast));
+				self.calls.push(Vec::new());
+				if let Err(e) = self.check(body, &ret, tnv) {
+					panic!("Function return not matched! {}", e)
+				}
+				tnv.pop();
+				let calls = self.calls.pop().unwrap();
+				let effect = match self.latent_effect(calls, **declared == Type::TotalT, name) {
+					Ok(effect) => effect,
+					Err(e) => panic!("{}", e),
+				};
+				/* a function declared divT is taken at its word */
+				let effect = if **declared == Type::DivT { Type::DivT } else { effect };
+				Type::FunT(Box::new(inpt), Box::new(ret), Box::new(effect))
+			},
+			AST::ArecC(name1, name2, type1, type2, body1, body2) => {
+				let type1 = self.annotation((**type1).clone());
+				let type2 = self.annotation((**type2).clone());
+				/* a recursive function may call itself forever, unless every
+				 * recursive call is on a smaller argument; then it is as total
+				 * as the other functions it calls
+				 */
+				let terminates = termination(name1, name2, body1).is_ok();
+				let effect = if terminates { self.fresh() } else { Type::DivT };
+				let fun_type = Type::FunT(Box::new(type1.clone()), Box::new(type2.clone()), Box::new(effect.clone()));
+				tnv.extend(name1.to_string(), fun_type.clone(), Binder::Node(ast));
+				/* the parameter is bound to the argument type, not the return type */
+				tnv.extend(name2.to_string(), type1, Binder::Node(ast));
+				self.calls.push(Vec::new());
+				let body_type = self.infer(body1, tnv);
+				let calls = self.calls.pop().unwrap();
+				if terminates {
+					let others = calls.into_iter().filter(|(callee_effect, _callee)| *callee_effect != effect).collect();
+					if let Err(e) = self.latent_effect(others, false, name1).and_then(|latent| self.unify(&effect, &latent)) {
+						panic!("Function effect not matched! {}", e)
+					}
+				}
+				if let Err(e) = self.fit(body1, &body_type, &type2, tnv) {
+					panic!("Function return not matched! {}", e)
+				}
+				tnv.pop();
+				tnv.pop();
+				/* the function is polymorphic in the rest of the program */
+				let scheme = self.generalize(&fun_type, tnv);
+				self.schemes.push((name1.to_string(), scheme.clone()));
+				tnv.extend(name1.to_string(), scheme, Binder::Node(ast));
+				let body_type = self.infer(body2, tnv);
+				tnv.pop();
+				body_type
+			},
+			AST::AletrecC(bindings, body) => {
+				/* every function of the group is visible in every body */
+				let mark = tnv.len();
+				let mut group = Vec::new();
+				for (fun_name, param, arg_type, ret_type, fun_body) in bindings {
+					if group.iter().any(|(other, _, _, _, _)| *other == fun_name) {
+						panic!("Duplicate function in letrecC: {}", fun_name);
+					}
+					let arg_type = self.annotation(arg_type.clone());
+					let ret_type = self.annotation(ret_type.clone());
+					tnv.extend(fun_name.to_string(), Type::FunT(Box::new(arg_type.clone()), Box::new(ret_type.clone()), Box::new(Type::DivT)), Binder::Node(ast));
+					group.push((fun_name, param, arg_type, ret_type, fun_body));
+				}
+				let mut fun_types = Vec::new();
+				for (fun_name, param, arg_type, ret_type, fun_body) in group {
+					tnv.extend(param.to_string(), arg_type.clone(), Binder::Node(ast));
+					self.calls.push(Vec::new());
+					let body_type = self.infer(fun_body, tnv);
+					self.calls.pop();
+					if let Err(e) = self.fit(fun_body, &body_type, &ret_type, tnv) {
+						panic!("Function return not matched in {}! {}", fun_name, e)
+					}
+					tnv.pop();
+					fun_types.push((fun_name, Type::FunT(Box::new(arg_type), Box::new(ret_type), Box::new(Type::DivT))));
+				}
+				/* generalized against the environment outside the group */
+				tnv.truncate(mark);
+				let schemes: Vec<(String, Type)> = fun_types.into_iter().map(|(fun_name, fun_type)| (fun_name.to_string(), self.generalize(&fun_type, tnv))).collect();
+				for (fun_name, scheme) in schemes {
+					self.schemes.push((fun_name.to_string(), scheme.clone()));
+					tnv.extend(fun_name.to_string(), scheme, Binder::Node(ast));
+				}
+				let body_type = self.infer(body, tnv);
+				tnv.truncate(mark);
+				body_type
+			},
+			AST::AletC(name, rhs, body) => {
+				/* generalize at let, so the name can be used at several types;
+				 * only values are generalized, otherwise a box of an unknown
+				 * type could be filled at one type and read at another
+				 */
+				let generalizable = is_value(rhs);
+				let rhs_type = self.infer(rhs, tnv);
+				let scheme = if generalizable {
+					self.generalize(&rhs_type, tnv)
+				} else {
+					self.resolve(&rhs_type)
+				};
+				self.schemes.push((name.to_string(), scheme.clone()));
+				tnv.extend(name.to_string(), scheme, Binder::Node(ast));
+				let body_type = self.infer(body, tnv);
+				tnv.pop();
+				body_type
+			},
+			AST::AtyLamC(a, body) => {
+				let mut outer = Vec::new();
+				for t in tnv.types() {
+					outer.extend(free_tyvars(&self.resolve(t)));
+				}
+				self.tyvars.push(a.to_string());
+				let body_type = self.infer(body, tnv);
+				self.tyvars.pop();
+				/* an inferred type outside may not mention the new variable */
+				if !outer.contains(a) {
+					for (name, t) in tnv.bindings() {
+						if free_tyvars(&self.resolve(t)).contains(a) {
+							panic!("Type variable {} escapes its scope through {}!", a, name)
+						}
+					}
+				}
+				Type::ForallT(a.to_string(), Box::new(body_type))
+			},
+			AST::AtyAppC(expr, t) => {
+				let t = self.annotation(t.clone());
+				let expr_type = self.infer(expr, tnv);
+				match self.resolve(&expr_type) {
+					Type::ForallT(a, body) => type_subst(&body, &a, &t),
+					/* types are erased at run time, so any instance of a dynT is one */
+					Type::DynT => Type::DynT,
+					other => panic!("Type application of a non-polymorphic expression: {}", principal(&other)),
+				}
+			},
+			AST::AtupleC(items) => {
+				Type::TupleT(items.iter().map(|item| self.infer(item, tnv)).collect())
+			},
+			AST::AprojC(tuple, index) => {
+				let tuple_type = self.infer(tuple, tnv);
+				match self.resolve(&tuple_type) {
+					Type::TupleT(items) => {
+						if *index < items.len() {
+							items[*index].clone()
+						} else {
+							panic!("Tuple index {} out of range for {}!", index, principal(&Type::TupleT(items)))
+						}
+					},
+					Type::VarT(_) => panic!("projC needs a tuple whose size is known!"),
+					/* a tuple cast has to know every item */
+					Type::DynT => panic!("projC needs a tuple whose size is known, not dyn!"),
+					other => panic!("Not a tuple: {}", principal(&other)),
+				}
+			},
+			AST::AinlC(expr, t) => self.inject(expr, t.clone(), true, tnv),
+			AST::AinrC(expr, t) => self.inject(expr, t.clone(), false, tnv),
+			AST::AcaseC(expr, left_name, left_body, right_name, right_body) => {
+				let expr_type = self.infer(expr, tnv);
+				/* a dynT scrutinee only needs to be some sum */
+				let (left, right) = if self.resolve(&expr_type) == Type::DynT {
+					(Type::DynT, Type::DynT)
+				} else {
+					(self.fresh(), self.fresh())
+				};
+				if let Err(e) = self.fit(expr, &expr_type, &Type::SumT(Box::new(left.clone()), Box::new(right.clone())), tnv) {
+					panic!("caseC needs a sum type! {}", e)
+				}
+				tnv.extend(left_name.to_string(), left, Binder::Node(ast));
+				let left_type = self.infer(left_body, tnv);
+				tnv.pop();
+				tnv.extend(right_name.to_string(), right, Binder::Node(ast));
+				let right_type = self.infer(right_body, tnv);
+				if let Err(e) = self.fit(right_body, &right_type, &left_type, tnv) {
+					panic!("Case branches not matched! {}", e)
+				}
+				tnv.pop();
+				left_type
+			},
+			AST::AemptyC(elem_type) => Type::ListT(Box::new(self.annotation(elem_type.clone()))),
+			AST::AconsC(first, rest) => {
+				let first_type = self.infer(first, tnv);
+				let rest_type = self.infer(rest, tnv);
+				let list_type = Type::ListT(Box::new(first_type));
+				if let Err(e) = self.fit(rest, &rest_type, &list_type, tnv) {
+					panic!("Invalid in cons! {}", e)
+				}
+				list_type
+			},
+			AST::AfirstC(list) => self.list_elem(list, tnv),
+			AST::ArestC(list) => Type::ListT(Box::new(self.list_elem(list, tnv))),
+			AST::AisEmptyC(list) => {
+				self.list_elem(list, tnv);
+				Type::BoolT
+			},
+			AST::AappC(fun, arg) => {
+				let fun_type = self.infer(fun, tnv);
+				let (param_type, ret_type, effect) = match self.resolve(&fun_type) {
+					Type::FunT(param_type, ret_type, effect) => (*param_type, *ret_type, *effect),
+					Type::VarT(_) => {
+						let param_type = self.fresh();
+						let ret_type = self.fresh();
+						let effect = self.fresh();
+						let expected = Type::FunT(Box::new(param_type.clone()), Box::new(ret_type.clone()), Box::new(effect.clone()));
+						if let Err(e) = self.unify(&fun_type, &expected) {
+							panic!("Function argument not matched! {}", e)
+						}
+						(param_type, ret_type, effect)
+					},
+					/* applying a dynT checks at run time that it is a function,
+					 * which could be any function */
+					Type::DynT => {
+						let dyn_fun = Type::FunT(Box::new(Type::DynT), Box::new(Type::DynT), Box::new(Type::DivT));
+						self.record_cast(fun, &Type::DynT, &dyn_fun, tnv);
+						(Type::DynT, Type::DynT, Type::DivT)
+					},
+					_ => panic!("Not a function!"),
+				};
+				let callee = match &**fun {
+					AST::AidC(id) => id.to_string(),
+					_ => ast_label(fun),
+				};
+				self.calls.last_mut().unwrap().push((effect, callee));
+				/* the argument is checked against the parameter, and may be a subtype of it */
+				if let Err(e) = self.check(arg, &param_type, tnv) {
+					panic!("Function argument not matched! {}", e)
+				}
+				ret_type
+			},
+			AST::ArecordC(fields) => {
+				let mut field_types = Vec::new();
+				for (name, value) in fields {
+					field_types.push((name.to_string(), self.infer(value, tnv)));
+				}
+				record_type(field_types)
+			},
+			AST::AgetC(record, name) => {
+				let record_type = self.infer(record, tnv);
+				match self.resolve(&record_type) {
+					Type::RecordT(fields) => match fields.iter().find(|(field, _t)| field == name) {
+						Some((_field, t)) => t.clone(),
+						None => panic!("Record {} has no field {}!", principal(&Type::RecordT(fields.clone())), name),
+					},
+					Type::VarT(_) => panic!("getC needs a record whose fields are known!"),
+					/* a dynT only needs to be a record with the field */
+					Type::DynT => {
+						self.record_cast(record, &Type::DynT, &Type::RecordT(vec![(name.to_string(), Type::DynT)]), tnv);
+						Type::DynT
+					},
+					other => panic!("Not a record: {}", principal(&other)),
+				}
+			},
+			AST::AdatatypeC(name, variants, body) => {
+				if !top_level {
+					panic!("datatypeC {} must be declared at the top level!", name)
+				}
+				if self.datatypes.contains_key(name) {
+					panic!("Duplicate datatype: {}", name)
+				}
+				for (variant, _fields) in variants.iter() {
+					if variants.iter().filter(|(v, _fields)| v == variant).count() > 1
+						|| self.datatypes.values().any(|others| others.iter().any(|(v, _fields)| v == variant)) {
+						panic!("Duplicate variant: {}", variant)
+					}
+				}
+				/* registered first, so the fields can refer to the datatype itself */
+				self.datatypes.insert(name.to_string(), variants.clone());
+				for (variant, fields) in variants {
+					for field in fields {
+						if !free_vars(&self.annotation(field.clone())).is_empty() {
+							panic!("The fields of {} must be fully annotated!", variant)
+						}
+					}
+				}
+				self.infer(body, tnv)
+			},
+			AST::AconC(variant, args) => {
+				let (name, fields) = self.variant(variant);
+				if fields.len() != args.len() {
+					panic!("{} expects {} fields but was given {}!", variant, fields.len(), args.len())
+				}
+				for (arg, field) in args.iter().zip(fields.iter()) {
+					let arg_type = self.infer(arg, tnv);
+					if let Err(e) = self.subtype(&arg_type, field).and_then(|_| self.dyn_effects(&arg_type, field)) {
+						panic!("Field of {} not matched! {}", variant, e)
+					}
+					self.record_cast(arg, &arg_type, field, tnv);
+				}
+				Type::NamedT(name)
+			},
+			AST::AmatchC(expr, arms) => {
+				let expr_type = self.infer(expr, tnv);
+				let name = match self.resolve(&expr_type) {
+					Type::NamedT(name) => name,
+					Type::VarT(_) | Type::DynT => {
+						/* the first variant tells which datatype is matched */
+						let name = match arms.iter().find_map(|(variant, _vars, _body)| variant.clone()) {
+							Some(variant) => self.variant(&variant).0,
+							None => panic!("matchC needs a datatype whose variants are known!"),
+						};
+						if let Err(e) = self.fit(expr, &expr_type, &Type::NamedT(name.to_string()), tnv) {
+							panic!("matchC needs a datatype! {}", e)
+						}
+						name
+					},
+					other => panic!("Not a datatype: {}", principal(&other)),
+				};
+				let variants = self.datatypes[&name].clone();
+				let mut covered: Vec<String> = Vec::new();
+				let mut wildcard = false;
+				let mut result_type: Option<Type> = None;
+				for (variant, vars, body) in arms {
+					let mark = tnv.len();
+					match variant {
+						Some(variant) => {
+							let fields = match variants.iter().find(|(v, _fields)| v == variant) {
+								Some((_variant, fields)) => fields.clone(),
+								None => panic!("{} is not a variant of {}!", variant, name),
+							};
+							if wildcard || covered.contains(variant) {
+								panic!("Redundant pattern: {}", variant)
+							}
+							if fields.len() != vars.len() {
+								panic!("{} has {} fields but the pattern binds {}!", variant, fields.len(), vars.len())
+							}
+							for (var, field) in vars.iter().zip(fields) {
+								if var != "_" {
+									tnv.extend(var.to_string(), field, Binder::Arm(variant));
+								}
+							}
+							covered.push(variant.to_string());
+						},
+						None => {
+							if wildcard || covered.len() == variants.len() {
+								panic!("Redundant pattern: _")
+							}
+							wildcard = true;
+						},
+					}
+					let body_type = self.infer(body, tnv);
+					tnv.truncate(mark);
+					result_type = match result_type {
+						None => Some(body_type),
+						Some(t) => match self.join(&t, &body_type) {
+							Ok(joined) => Some(joined),
+							Err(e) => panic!("Match arms not matched! {}", e),
+						},
+					};
+				}
+				if !wildcard {
+					let missing: Vec<String> = variants.iter().map(|(v, _fields)| v.to_string()).filter(|v| !covered.contains(v)).collect();
+					if !missing.is_empty() {
+						panic!("Non-exhaustive patterns in matchC, missing: {}", missing.join(", "))
+					}
+				}
+				match result_type {
+					Some(t) => t,
+					None => panic!("matchC needs at least one arm!"),
+				}
+			},
+			AST::AfoldC(t, expr) => {
+				let mu_type = self.annotation(t.clone());
+				let unfolded = match &mu_type {
+					Type::MuT(a, body) => type_subst(body, a, &mu_type),
+					other => panic!("foldC must be annotated with a muT type, not {}!", principal(other)),
+				};
+				let expr_type = self.infer(expr, tnv);
+				if let Err(e) = self.subtype(&expr_type, &unfolded).and_then(|_| self.dyn_effects(&expr_type, &unfolded)) {
+					panic!("Fold not matched! {}", e)
+				}
+				self.record_cast(expr, &expr_type, &unfolded, tnv);
+				mu_type
+			},
+			AST::AunfoldC(expr) => {
+				let expr_type = self.infer(expr, tnv);
+				match self.resolve(&expr_type) {
+					/* a function unfolded may be applied to its own fold */
+					Type::MuT(a, body) => diverging(&type_subst(&body, &a, &Type::MuT(a.to_string(), body.clone()))),
+					Type::VarT(_) => panic!("unfoldC needs a recursive type that is known!"),
+					/* folds are erased at run time, so unfolding a dynT gives one */
+					Type::DynT => Type::DynT,
+					other => panic!("Not a recursive type: {}", principal(&other)),
+				}
+			},
+			AST::AboxC(expr) => {
+				let elem_type = self.infer(expr, tnv);
+				Type::RefT(Box::new(diverging(&self.resolve(&elem_type))))
+			},
+			AST::AunboxC(expr) => self.ref_elem(expr, tnv),
+			AST::AsetboxC(expr, value) => {
+				let elem_type = self.ref_elem(expr, tnv);
+				let value_type = self.infer(value, tnv);
+				/* boxes are invariant, the value must have the element type,
+				 * where any function stored may diverge
+				 */
+				let value_type = diverging(&self.resolve(&value_type));
+				if let Err(e) = self.fit(value, &value_type, &elem_type, tnv) {
+					panic!("setboxC value not matched! {}", e)
+				}
+				Type::UnitT
+			},
+			AST::AcastC(expr, _from, to, _positive, _negative) => {
+				self.infer(expr, tnv);
+				to.clone()
+			},
+			AST::AasC(expr, t) => {
+				let ascribed = self.annotation(t.clone());
+				if let Err(e) = self.check(expr, &ascribed, tnv) {
+					panic!("Ascription not matched! {}", e)
+				}
+				ascribed
+			},
+			AST::AseqC(exprs) => {
+				let mut last_type = Type::UnitT;
+				for expr in exprs {
+					last_type = self.infer(expr, tnv);
+				}
+				last_type
+			},
+			AST::AstrC(_s) => Type::StrT,
+			AST::AholeC(name) => {
+				let expected = self.fresh();
+				let mut scope: Vec<(String, Type)> = tnv.bindings().map(|(var, t)| (var.to_string(), t.clone())).collect();
+				scope.sort_by(|a, b| a.0.cmp(&b.0));
+				self.holes.push(Hole { name: name.to_string(), expected: expected.clone(), scope });
+				expected
+			},
+			/* these cases will not happen */
+			AST::None => Type::BoolT,
+			AST::AtypeC(_t) => Type::BoolT,
+		}
+	}
+}
+
+pub fn apply_subst(subst: &HashMap<usize, Type>, t: &Type) -> Type {
+	match t {
+		Type::VarT(v) => match subst.get(v) {
+			Some(bound) => apply_subst(subst, bound),
+			None => t.clone(),
+		},
+		Type::SchemeT(vars, body) => {
+			/* the quantified variables are not touched */
+			let mut inner = subst.clone();
+			for v in vars.iter() {
+				inner.remove(v);
+			}
+			Type::SchemeT(vars.clone(), Box::new(apply_subst(&inner, body)))
+		},
+		_ => t.map_children(&mut |child| apply_subst(subst, child)),
+	}
+}
+
+/* the inference variables in the order they appear, without duplicates */
+pub fn free_vars(t: &Type) -> Vec<usize> {
+	match t {
+		Type::VarT(v) => vec![*v],
+		Type::SchemeT(bound, body) => free_vars(body).into_iter().filter(|v| !bound.contains(v)).collect(),
+		_ => {
+			let mut vars = Vec::new();
+			for child in t.children() {
+				for v in free_vars(child) {
+					if !vars.contains(&v) {
+						vars.push(v);
+					}
+				}
+			}
+			vars
+		},
+	}
+}
+
+/* the datatypes a type refers to */
+pub fn named_types(t: &Type) -> Vec<String> {
+	match t {
+		Type::NamedT(name) => vec![name.to_string()],
+		_ => t.children().into_iter().flat_map(named_types).collect(),
+	}
+}
+
+/* the named type variables that are not bound by a forallT */
+pub fn free_tyvars(t: &Type) -> Vec<String> {
+	match t {
+		Type::TvarT(a) => vec![a.to_string()],
+		Type::ForallT(a, body) | Type::MuT(a, body) => free_tyvars(body).into_iter().filter(|v| v != a).collect(),
+		_ => t.children().into_iter().flat_map(free_tyvars).collect(),
+	}
+}
+
+/* a', a'', ... until the name is not in `avoid` */
+pub fn fresh_tyvar(base: &str, avoid: &[String]) -> String {
+	let mut name = base.to_string();
+	while avoid.contains(&name) {
+		name.push('\'');
+	}
+	name
+}
+
+/* capture-avoiding substitution t[a := replacement] */
+pub fn type_subst(t: &Type, a: &str, replacement: &Type) -> Type {
+	match t {
+		Type::TvarT(b) if b == a => replacement.clone(),
+		Type::ForallT(b, body) | Type::MuT(b, body) => {
+			if b == a {
+				t.clone()
+			} else if free_tyvars(replacement).contains(b) {
+				/* rename the bound variable so the replacement is not captured */
+				let mut avoid = free_tyvars(replacement);
+				avoid.extend(free_tyvars(body));
+				avoid.push(a.to_string());
+				let fresh = fresh_tyvar(b, &avoid);
+				let renamed = type_subst(body, b, &Type::TvarT(fresh.to_string()));
+				t.rebind(fresh, type_subst(&renamed, a, replacement))
+			} else {
+				t.rebind(b.to_string(), type_subst(body, a, replacement))
+			}
+		},
+		_ => t.map_children(&mut |child| type_subst(child, a, replacement)),
+	}
+}
+
+pub fn rename_vars(t: &Type, renaming: &HashMap<usize, Type>) -> Type {
+	match t {
+		Type::VarT(v) => match renaming.get(v) {
+			Some(r) => r.clone(),
+			None => t.clone(),
+		},
+		Type::SchemeT(bound, body) => {
+			let mut inner = renaming.clone();
+			for v in bound.iter() {
+				inner.remove(v);
+			}
+			Type::SchemeT(bound.clone(), Box::new(rename_vars(body, &inner)))
+		},
+		_ => t.map_children(&mut |child| rename_vars(child, renaming)),
+	}
+}
+
+/* the variables standing for the effect of a FunT, which are not printed */
+pub fn effect_vars(t: &Type) -> Vec<usize> {
+	match t {
+		Type::FunT(a, b, effect) => {
+			let mut vars = effect_vars(a);
+			vars.extend(effect_vars(b));
+			if let Type::VarT(v) = **effect {
+				vars.push(v);
+			}
+			vars
+		},
+		_ => t.children().into_iter().flat_map(effect_vars).collect(),
+	}
+}
+
+/* number the type variables 0, 1, 2, ... in the order they appear,
+ * so they are printed as 'a, 'b, 'c, ...; effect variables come last,
+ * so the printed ones have no gaps
+ */
+pub fn principal(t: &Type) -> Type {
+	let mut renaming = HashMap::new();
+	let mut vars = match t {
+		Type::SchemeT(_bound, body) => free_vars(body),
+		_ => free_vars(t),
+	};
+	let effects = effect_vars(t);
+	vars.sort_by_key(|v| effects.contains(v));
+	for v in vars {
+		if !renaming.contains_key(&v) {
+			let next = renaming.len();
+			renaming.insert(v, Type::VarT(next));
+		}
+	}
+	match t {
+		Type::SchemeT(bound, body) => {
+			let bound = bound.iter().map(|v| match renaming.get(v) { Some(Type::VarT(n)) => *n, _ => *v }).collect();
+			Type::SchemeT(bound, Box::new(rename_vars(body, &renaming)))
+		},
+		_ => rename_vars(t, &renaming),
+	}
+}
+
+/* the binder of an idC, for blame labels */
+fn ast_binder<'a>(ast: &AST, tnv: &Env<'a>) -> Option<Binder<'a>> {
+	match ast {
+		AST::AidC(id) => tnv.binder(id),
+		_ => None,
+	}
+}
+
+/* whether a dynT occurs anywhere in the type */
+pub fn has_dyn(t: &Type) -> bool {
+	*t == Type::DynT || t.children().into_iter().any(has_dyn)
+}
+
+/* the type with every function it returns possibly diverging: a function
+ * read back from a box or a recursive type may end up calling itself.
+ * Nested boxes are left alone, they get the same view when read.
+ */
+pub fn diverging(t: &Type) -> Type {
+	match t {
+		Type::FunT(param, ret, _) => Type::FunT(param.clone(), Box::new(diverging(ret)), Box::new(Type::DivT)),
+		Type::RefT(_) => t.clone(),
+		_ => t.map_children(&mut diverging),
+	}
+}
+
+/* how a node is shown in the typed tree: its keyword, with the names and
+ * numbers it carries but without its sub-expressions
+ */
+pub fn ast_label(ast: &AST) -> String {
+	match ast {
+		AST::AnumC(i) => format!("numC({})", i),
+		AST::AidC(s) => format!("idC(\"{}\")", s),
+		AST::AtrueC(_) => "trueC".to_string(),
+		AST::AfalseC(_) => "falseC".to_string(),
+		AST::AstrC(s) => format!("'{}'", s),
+		AST::AprimC(name, _) => name.to_string(),
+		AST::AifC(_, _, _) => "ifC".to_string(),
+		AST::AappC(_, _) => "appC".to_string(),
+		AST::AfdC(name, _, _, _, _) => format!("fdC(\"{}\")", name),
+		AST::ArecC(name, param, _, _, _, _) => format!("recC(\"{}\", \"{}\")", name, param),
+		AST::AletrecC(bindings, _) => {
+			let names: Vec<String> = bindings.iter().map(|(name, _, _, _, _)| format!("\"{}\"", name)).collect();
+			format!("letrecC({})", names.join(", "))
+		},
+		AST::AletC(name, _, _) => format!("letC(\"{}\")", name),
+		AST::AtyLamC(a, _) => format!("tyLamC(\"{}\")", a),
+		AST::AtyAppC(_, t) => format!("tyAppC({})", t),
+		AST::AtupleC(_) => "tupleC".to_string(),
+		AST::AprojC(_, i) => format!("projC({})", i),
+		AST::AinlC(_, _) => "inlC".to_string(),
+		AST::AinrC(_, _) => "inrC".to_string(),
+		AST::AcaseC(_, left, _, right, _) => format!("caseC(\"{}\", \"{}\")", left, right),
+		AST::AemptyC(_) => "emptyC".to_string(),
+		AST::AconsC(_, _) => "consC".to_string(),
+		AST::AfirstC(_) => "firstC".to_string(),
+		AST::ArestC(_) => "restC".to_string(),
+		AST::AisEmptyC(_) => "isEmptyC".to_string(),
+		AST::ArecordC(_) => "recordC".to_string(),
+		AST::AgetC(_, field) => format!("getC(\"{}\")", field),
+		AST::AdatatypeC(name, _, _) => format!("datatypeC(\"{}\")", name),
+		AST::AconC(variant, _) => format!("conC(\"{}\")", variant),
+		AST::AmatchC(_, _) => "matchC".to_string(),
+		AST::AfoldC(_, _) => "foldC".to_string(),
+		AST::AunfoldC(_) => "unfoldC".to_string(),
+		AST::AboxC(_) => "boxC".to_string(),
+		AST::AunboxC(_) => "unboxC".to_string(),
+		AST::AsetboxC(_, _) => "setboxC".to_string(),
+		AST::AseqC(_) => "seqC".to_string(),
+		AST::AasC(_, _) => "asC".to_string(),
+		AST::AholeC(name) => format!("?{}", name),
+		AST::AcastC(_, from, to, _, _) => format!("cast({} => {})", from, to),
+		AST::None => "None".to_string(),
+		AST::AtypeC(t) => format!("{}", t),
+	}
+}
+
+/* syntactic values, whose type is safe to generalize */
+pub fn is_value(ast: &AST) -> bool {
+	match ast {
+		AST::AnumC(_) | AST::AtrueC(_) | AST::AfalseC(_) | AST::AidC(_) | AST::AfdC(_, _, _, _, _) | AST::AstrC(_) |
+		AST::AtyLamC(_, _) | AST::AemptyC(_) => true,
+		AST::AtupleC(items) => items.iter().all(is_value),
+		AST::ArecordC(fields) => fields.iter().all(|(_name, value)| is_value(value)),
+		AST::AinlC(expr, _) | AST::AinrC(expr, _) | AST::AfoldC(_, expr) | AST::AasC(expr, _) => is_value(expr),
+		AST::AconsC(first, rest) => is_value(first) && is_value(rest),
+		AST::AconC(_, fields) => fields.iter().all(is_value),
+		_ => false,
+	}
+}
+
+/* Levenshtein distance: insertions, deletions and substitutions */
+pub fn edit_distance(a: &str, b: &str) -> usize {
+	let b: Vec<char> = b.chars().collect();
+	let mut row: Vec<usize> = (0..=b.len()).collect();
+	for (i, ca) in a.chars().enumerate() {
+		let mut diagonal = row[0];
+		row[0] = i + 1;
+		for (j, cb) in b.iter().enumerate() {
+			let above = row[j + 1];
+			row[j + 1] = if ca == *cb { diagonal } else { 1 + diagonal.min(above).min(row[j]) };
+			diagonal = above;
+		}
+	}
+	row[b.len()]
+}
+
+/* --------------- Termination ---------------
+ * A recC terminates when every recursive call in its body is on a smaller
+ * argument: restC of the parameter, or the parameter minus a positive
+ * number under an ifC guard that the parameter is positive, such as
+ * ltC(numC(0), idC("n")). Such a recC is total; the others may diverge,
+ * and the unproven-termination lint says why.
+ */
+pub fn termination(name: &str, param: &str, body: &AST) -> Result<(), String> {
+	calls_decrease(name, Some(param), body, None)
+}
+
+/* `param` is None where the parameter is shadowed, and `bound` is the least
+ * value the enclosing guards allow for it
+ */
+fn calls_decrease(name: &str, param: Option<&str>, ast: &AST, bound: Option<i32>) -> Result<(), String> {
+	match ast {
+		AST::AappC(fun, arg) if matches!(&**fun, AST::AidC(id) if id == name) => {
+			if !smaller(arg, param, bound) {
+				let reason = match (&**arg, param) {
+					(AST::AprimC("minusC", args), Some(param)) if args[0] == AST::AidC(param.to_string()) && bound.unwrap_or(0) < 1 =>
+						format!("{} is called on {} without a guard that {} is positive", name, show_arg(arg), param),
+					(_, Some(param)) => format!("{} is called on {}, which is not smaller than {}", name, show_arg(arg), param),
+					(_, None) => format!("{} is called where its parameter is shadowed", name),
+				};
+				return Err(reason)
+			}
+			calls_decrease(name, param, arg, bound)
+		},
+		AST::AidC(id) if id == name => Err(format!("{} is used as a value, so its calls cannot be followed", name)),
+		AST::AifC(ifc, thenc, elsec) => {
+			calls_decrease(name, param, ifc, bound)?;
+			let then_bound = param.and_then(|param| lower_bound(ifc, param, true));
+			let else_bound = param.and_then(|param| lower_bound(ifc, param, false));
+			calls_decrease(name, param, thenc, bound.max(then_bound))?;
+			calls_decrease(name, param, elsec, bound.max(else_bound))
+		},
+		_ => {
+			for (bound_names, child) in ast.scoped_children() {
+				if bound_names.iter().any(|bound_name| *bound_name == name) {
+					/* the calls in here are to another function */
+					continue
+				}
+				let shadowed = param.is_some_and(|param| bound_names.iter().any(|bound_name| *bound_name == param));
+				calls_decrease(name, if shadowed { None } else { param }, child, bound)?;
+			}
+			Ok(())
+		},
+	}
+}
+
+/* the least value of the parameter when `cond` evaluates to `holds` */
+fn lower_bound(cond: &AST, param: &str, holds: bool) -> Option<i32> {
+	let is_param = |ast: &AST| matches!(ast, AST::AidC(id) if id == param);
+	match (cond, holds) {
+		(AST::AprimC("ltC", args), true) => match (&args[0], &args[1]) {
+			(AST::AnumC(k), other) if is_param(other) => k.checked_add(1),
+			_ => None,
+		},
+		(AST::AprimC("leC", args), true) => match (&args[0], &args[1]) {
+			(AST::AnumC(k), other) if is_param(other) => Some(*k),
+			_ => None,
+		},
+		(AST::AprimC("ltC", args), false) => match (&args[0], &args[1]) {
+			(other, AST::AnumC(k)) if is_param(other) => Some(*k),
+			_ => None,
+		},
+		(AST::AprimC("leC", args), false) => match (&args[0], &args[1]) {
+			(other, AST::AnumC(k)) if is_param(other) => k.checked_add(1),
+			_ => None,
+		},
+		(AST::AprimC("notC", args), _) => lower_bound(&args[0], param, !holds),
+		(AST::AprimC("andC", args), true) | (AST::AprimC("orC", args), false) =>
+			lower_bound(&args[0], param, holds).max(lower_bound(&args[1], param, holds)),
+		_ => None,
+	}
+}
+
+/* restC of the parameter, or the parameter minus k > 0 when it is at least 1 */
+fn smaller(arg: &AST, param: Option<&str>, bound: Option<i32>) -> bool {
+	let param = match param {
+		Some(param) => param,
+		None => return false,
+	};
+	match arg {
+		AST::ArestC(list) => matches!(&**list, AST::AidC(id) if id == param) || smaller(list, Some(param), bound),
+		AST::AprimC("minusC", args) => match (&args[0], &args[1]) {
+			(AST::AidC(id), AST::AnumC(k)) => id == param && *k >= 1 && bound.unwrap_or(0) >= 1,
+			_ => false,
+		},
+		_ => false,
+	}
+}
+
+/* a recursive call's argument, for the unproven-termination lint */
+fn show_arg(arg: &AST) -> String {
+	match arg {
+		AST::AidC(id) => id.to_string(),
+		AST::AnumC(i) => i.to_string(),
+		AST::AprimC("minusC", args) => format!("{} - {}", show_arg(&args[0]), show_arg(&args[1])),
+		AST::AprimC("plusC", args) => format!("{} + {}", show_arg(&args[0]), show_arg(&args[1])),
+		AST::ArestC(list) => format!("restC({})", show_arg(list)),
+		_ => ast_label(arg),
+	}
+}
+
+/* --------------- Lint Pass ---------------
+ * Warnings about programs that type check but are probably not what was
+ * meant. Every lint has an ID, so each one can be allowed or denied.
+ */
+pub static LINTS: &[(&str, &str)] = &[
+	("unused-param", "an fdC parameter is never used in its body"),
+	("shadowing", "a binder hides a name bound outside it"),
+	("constant-condition", "an ifC condition is trueC or falseC, so one branch is dead"),
+	("self-comparison", "eqC compares an expression with itself"),
+	("unproven-termination", "a recC calls itself on an argument that is not shown to be smaller"),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lint {
+	pub id: &'static str,
+	pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+	Allow,
+	Warn,
+	Deny,
+}
+
+/* the level of every lint, Warn unless changed */
+pub struct LintLevels {
+	levels: HashMap<&'static str, Level>,
+}
+
+impl Default for LintLevels {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl LintLevels {
+	pub fn new() -> LintLevels {
+		LintLevels { levels: LINTS.iter().map(|(id, _description)| (*id, Level::Warn)).collect() }
+	}
+
+	pub fn set(&mut self, id: &str, level: Level) {
+		match LINTS.iter().find(|(known, _description)| *known == id) {
+			Some((known, _description)) => { self.levels.insert(known, level); },
+			None => panic!("Unknown lint: {}", id),
+		}
+	}
+
+	pub fn level(&self, id: &str) -> Level {
+		self.levels[id]
+	}
+}
+
+pub fn lint(ast: &AST) -> Vec<Lint> {
+	let mut lints = Vec::new();
+	lint_node(ast, &mut Vec::new(), &mut lints);
+	lints
+}
+
+/* `scope` holds every enclosing binder: its name, the node that bound
+ * it, and whether an idC has referred to it yet
+ */
+fn lint_node(ast: &AST, scope: &mut Vec<(String, String, bool)>, lints: &mut Vec<Lint>) {
+	match ast {
+		AST::AidC(id) => {
+			if let Some(binder) = scope.iter_mut().rev().find(|(name, _binder, _used)| name == id) {
+				binder.2 = true;
+			}
+		},
+		AST::AifC(cond, _, _) => {
+			if let AST::AtrueC(_) | AST::AfalseC(_) = **cond {
+				let dead = if let AST::AtrueC(_) = **cond { "else" } else { "then" };
+				lints.push(Lint { id: "constant-condition", message: format!("ifC({}, ...) never takes its {} branch", ast_label(cond), dead) });
+			}
+		},
+		AST::AprimC(name, args) if (*name == "eqC" || *name == "strEqC") && args.len() == 2 && args[0] == args[1] => {
+			lints.push(Lint { id: "self-comparison", message: format!("{} compares {} with itself", name, ast_label(&args[0])) });
+		},
+		AST::ArecC(name, param, _, _, body, _) => {
+			if let Err(reason) = termination(name, param, body) {
+				lints.push(Lint { id: "unproven-termination", message: format!("{} may not terminate: {}", ast_label(ast), reason) });
+			}
+		},
+		_ => {},
+	}
+	let label = ast_label(ast);
+	for (bound, child) in ast.scoped_children() {
+		let mark = scope.len();
+		for name in bound {
+			if let Some((_name, outer, _used)) = scope.iter().rev().find(|(other, _binder, _used)| other == name) {
+				/* a binder seen by several children is reported once */
+				let shadowing = Lint { id: "shadowing", message: format!("{} bound by {} shadows an outer {} bound by {}", name, label, name, outer) };
+				if !lints.contains(&shadowing) {
+					lints.push(shadowing);
+				}
+			}
+			scope.push((name.to_string(), label.to_string(), false));
+		}
+		lint_node(child, scope, lints);
+		if let AST::AfdC(param, _, _, _, _) = ast {
+			if !scope[mark].2 && !param.starts_with('_') {
+				lints.push(Lint { id: "unused-param", message: format!("parameter {} of {} is never used", param, label) });
+			}
+		}
+		scope.truncate(mark);
+	}
+}
+
+/* --------------- Evaluator ---------------
+ * Runs an elaborated program (Checked::program). Values borrow the names
+ * and bodies of the AST they came from. A castC checks the shape of a value
+ * leaving dynT and wraps functions in a Proxy that casts their argument and
+ * result when applied. A bad value is blamed on the positive label of the
+ * cast, and a bad argument, which flows the other way, on the negative one.
+ */
+#[derive(Debug, Clone)]
+pub enum Value<'a> {
+	Num(i32),
+	Bool(bool),
+	Str(String),
+	Unit,
+	Closure(&'a String, &'a AST, Rc<Scope<'a>>),
+	Proxy(Rc<Value<'a>>, Type, Type, &'a String, &'a String), /* a function cast from one FunT to another, with its blame labels */
+	Tuple(Vec<Value<'a>>),
+	Record(Vec<(&'a String, Value<'a>)>),
+	Inl(Box<Value<'a>>),
+	Inr(Box<Value<'a>>),
+	List(Vec<Value<'a>>),
+	Con(&'a String, Vec<Value<'a>>),
+	Box(Rc<RefCell<Value<'a>>>),
+}
+
+impl fmt::Display for Value<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let join = |items: &[Value]| items.iter().map(|item| item.to_string()).collect::<Vec<String>>().join(", ");
+		match self {
+			Value::Num(i) => write!(f, "{}", i),
+			Value::Bool(b) => write!(f, "{}", b),
+			Value::Str(s) => write!(f, "'{}'", s),
+			Value::Unit => write!(f, "unit"),
+			Value::Closure(_, _, _) | Value::Proxy(_, _, _, _, _) => write!(f, "<function>"),
+			Value::Tuple(items) => write!(f, "({})", join(items)),
+			Value::Record(fields) => {
+				let fields: Vec<String> = fields.iter().map(|(name, value)| format!("{}: {}", name, value)).collect();
+				write!(f, "{{{}}}", fields.join(", "))
+			},
+			Value::Inl(v) => write!(f, "inl({})", v),
+			Value::Inr(v) => write!(f, "inr({})", v),
+			Value::List(items) => write!(f, "[{}]", join(items)),
+			Value::Con(variant, fields) if fields.is_empty() => write!(f, "{}", variant),
+			Value::Con(variant, fields) => write!(f, "{}({})", variant, join(fields)),
+			Value::Box(cell) => write!(f, "box({})", cell.borrow()),
+		}
+	}
+}
+
+/* the run-time environment; recC and letrecC fill their cell after the
+ * closure that refers to it is built
+ */
+#[derive(Debug)]
+pub enum Scope<'a> {
+	Empty,
+	Bind(&'a String, Rc<RefCell<Value<'a>>>, Rc<Scope<'a>>),
+}
+
+impl<'a> Scope<'a> {
+	fn lookup(&self, id: &str) -> Value<'a> {
+		let mut scope = self;
+		loop {
+			match scope {
+				Scope::Empty => panic!("Unexpected identifier at run time: {}", id),
+				Scope::Bind(name, cell, _) if *name == id => return cell.borrow().clone(),
+				Scope::Bind(_, _, next) => scope = next,
+			}
+		}
+	}
+}
+
+fn bind<'a>(scope: &Rc<Scope<'a>>, name: &'a String, value: Value<'a>) -> Rc<Scope<'a>> {
+	Rc::new(Scope::Bind(name, Rc::new(RefCell::new(value)), scope.clone()))
+}
+
+fn num(v: &Value) -> i32 {
+	match v {
+		Value::Num(i) => *i,
+		_ => panic!("Expected a number, but the value is {}", v),
+	}
+}
+
+fn boolean(v: &Value) -> bool {
+	match v {
+		Value::Bool(b) => *b,
+		_ => panic!("Expected a bool, but the value is {}", v),
+	}
+}
+
+fn string(v: &Value) -> String {
+	match v {
+		Value::Str(s) => s.to_string(),
+		_ => panic!("Expected a string, but the value is {}", v),
+	}
+}
+
+/* eqC at run time; boxes are equal only to themselves */
+fn values_equal<'a>(a: &Value<'a>, b: &Value<'a>) -> bool {
+	let all_equal = |xs: &[Value<'a>], ys: &[Value<'a>]| xs.len() == ys.len() && xs.iter().zip(ys.iter()).all(|(x, y)| values_equal(x, y));
+	match (a, b) {
+		(Value::Num(x), Value::Num(y)) => x == y,
+		(Value::Bool(x), Value::Bool(y)) => x == y,
+		(Value::Str(x), Value::Str(y)) => x == y,
+		(Value::Unit, Value::Unit) => true,
+		(Value::Tuple(xs), Value::Tuple(ys)) | (Value::List(xs), Value::List(ys)) => all_equal(xs, ys),
+		(Value::Record(xs), Value::Record(ys)) => xs.len() == ys.len() &&
+			xs.iter().all(|(name, x)| ys.iter().any(|(other, y)| name == other && values_equal(x, y))),
+		(Value::Inl(x), Value::Inl(y)) | (Value::Inr(x), Value::Inr(y)) => values_equal(x, y),
+		(Value::Con(v1, xs), Value::Con(v2, ys)) => v1 == v2 && all_equal(xs, ys),
+		(Value::Box(x), Value::Box(y)) => Rc::ptr_eq(x, y),
+		(Value::Closure(_, _, _) | Value::Proxy(_, _, _, _, _), _) | (_, Value::Closure(_, _, _) | Value::Proxy(_, _, _, _, _)) =>
+			panic!("Functions cannot be compared!"),
+		_ => false,
+	}
+}
+
+/* whether the value has the outermost shape of the type */
+fn fits(v: &Value, t: &Type) -> bool {
+	match (v, t) {
+		(_, Type::DynT) => true,
+		(Value::Num(_), Type::NumT) | (Value::Bool(_), Type::BoolT) | (Value::Str(_), Type::StrT) | (Value::Unit, Type::UnitT) => true,
+		(Value::Closure(_, _, _) | Value::Proxy(_, _, _, _, _), Type::FunT(_, _, _)) => true,
+		(Value::Tuple(items), Type::TupleT(types)) => items.len() == types.len(),
+		(Value::Record(fields), Type::RecordT(types)) => types.iter().all(|(name, _)| fields.iter().any(|(other, _)| *other == name)),
+		(Value::Inl(_) | Value::Inr(_), Type::SumT(_, _)) | (Value::List(_), Type::ListT(_)) | (Value::Box(_), Type::RefT(_)) |
+		(Value::Con(_, _), Type::NamedT(_)) => true,
+		/* type variables, foralls and recursive types are not checked */
+		(_, Type::VarT(_) | Type::TvarT(_) | Type::ForallT(_, _) | Type::MuT(_, _) | Type::SchemeT(_, _)) => true,
+		_ => false,
+	}
+}
+
+/* dynT on one side of a cast takes the outermost shape of the other */
+fn dyn_shape(t: &Type, other: &Type) -> Type {
+	match t {
+		Type::DynT => other.map_children(&mut |_| Type::DynT),
+		_ => t.clone(),
+	}
+}
+
+/* cast a value from one type to a consistent one */
+pub fn cast<'a>(v: Value<'a>, from: &Type, to: &Type, positive: &'a String, negative: &'a String) -> Value<'a> {
+	if from == to {
+		return v
+	}
+	if !fits(&v, to) {
+		panic!("Blame {}: expected {}, but the value is {}", positive, to, v)
+	}
+	let from = dyn_shape(from, to);
+	let to = dyn_shape(to, &from);
+	match (v, &from, &to) {
+		(v @ (Value::Closure(_, _, _) | Value::Proxy(_, _, _, _, _)), Type::FunT(_, _, _), Type::FunT(_, _, _)) =>
+			Value::Proxy(Rc::new(v), from.clone(), to.clone(), positive, negative),
+		(Value::Tuple(items), Type::TupleT(from_items), Type::TupleT(to_items)) => Value::Tuple(
+			items.into_iter().zip(from_items.iter().zip(to_items.iter())).map(|(item, (f, t))| cast(item, f, t, positive, negative)).collect()
+		),
+		(Value::List(items), Type::ListT(f), Type::ListT(t)) => Value::List(items.into_iter().map(|item| cast(item, f, t, positive, negative)).collect()),
+		(Value::Inl(v), Type::SumT(f, _), Type::SumT(t, _)) => Value::Inl(Box::new(cast(*v, f, t, positive, negative))),
+		(Value::Inr(v), Type::SumT(_, f), Type::SumT(_, t)) => Value::Inr(Box::new(cast(*v, f, t, positive, negative))),
+		(Value::Record(fields), Type::RecordT(from_fields), Type::RecordT(to_fields)) => {
+			let field_type = |types: &Vec<(String, Type)>, name: &String| types.iter().find(|(other, _)| other == name).map(|(_, t)| t.clone()).unwrap_or(Type::DynT);
+			Value::Record(fields.into_iter().map(|(name, v)| (name, cast(v, &field_type(from_fields, name), &field_type(to_fields, name), positive, negative))).collect())
+		},
+		/* boxes keep their identity, so their contents are not cast */
+		(v, _, _) => v,
+	}
+}
+
+pub fn apply<'a>(fun: Value<'a>, arg: Value<'a>) -> Value<'a> {
+	match fun {
+		Value::Closure(param, body, scope) => eval_in(body, &bind(&scope, param, arg)),
+		Value::Proxy(inner, Type::FunT(from_param, from_ret, _), Type::FunT(to_param, to_ret, _), positive, negative) => {
+			/* the argument flows in, against the cast, so the blame is swapped */
+			let arg = cast(arg, &to_param, &from_param, negative, positive);
+			cast(apply((*inner).clone(), arg), &from_ret, &to_ret, positive, negative)
+		},
+		_ => panic!("Not a function! {}", fun),
+	}
+}
+
+pub fn eval(ast: &AST) -> Value<'_> {
+	eval_in(ast, &Rc::new(Scope::Empty))
+}
+
+fn eval_in<'a>(ast: &'a AST, scope: &Rc<Scope<'a>>) -> Value<'a> {
+	match ast {
+		AST::AnumC(i) => Value::Num(*i),
+		AST::AtrueC(_) => Value::Bool(true),
+		AST::AfalseC(_) => Value::Bool(false),
+		AST::AstrC(s) => Value::Str(s.to_string()),
+		AST::AidC(id) => scope.lookup(id),
+		AST::AprimC(name, args) => {
+			let args: Vec<Value> = args.iter().map(|arg| eval_in(arg, scope)).collect();
+			(primitive(name).unwrap().run)(&args)
+		},
+		AST::AifC(ifc, thenc, elsec) => {
+			if boolean(&eval_in(ifc, scope)) { eval_in(thenc, scope) } else { eval_in(elsec, scope) }
+		},
+		AST::AappC(fun, arg) => {
+			let fun = eval_in(fun, scope);
+			apply(fun, eval_in(arg, scope))
+		},
+		AST::AfdC(param, _, _, _, body) => Value::Closure(param, body, scope.clone()),
+		AST::ArecC(name, param, _, _, body1, body2) => {
+			let rec_scope = bind(scope, name, Value::Unit);
+			if let Scope::Bind(_, cell, _) = &*rec_scope {
+				*cell.borrow_mut() = Value::Closure(param, body1, rec_scope.clone());
+			}
+			eval_in(body2, &rec_scope)
+		},
+		AST::AletrecC(bindings, body) => {
+			let mut rec_scope = scope.clone();
+			for (name, _, _, _, _) in bindings.iter() {
+				rec_scope = bind(&rec_scope, name, Value::Unit);
+			}
+			for (name, param, _, _, fun_body) in bindings.iter() {
+				let closure = Value::Closure(param, fun_body, rec_scope.clone());
+				let mut current = &*rec_scope;
+				while let Scope::Bind(other, cell, next) = current {
+					if other == &name {
+						*cell.borrow_mut() = closure;
+						break
+					}
+					current = next;
+				}
+			}
+			eval_in(body, &rec_scope)
+		},
+		AST::AletC(name, rhs, body) => {
+			let value = eval_in(rhs, scope);
+			eval_in(body, &bind(scope, name, value))
+		},
+		AST::AtyLamC(_, expr) | AST::AtyAppC(expr, _) | AST::AfoldC(_, expr) | AST::AunfoldC(expr) | AST::AasC(expr, _) |
+		AST::AdatatypeC(_, _, expr) => eval_in(expr, scope),
+		AST::AcastC(expr, from, to, positive, negative) => cast(eval_in(expr, scope), from, to, positive, negative),
+		AST::AtupleC(items) => Value::Tuple(items.iter().map(|item| eval_in(item, scope)).collect()),
+		AST::AprojC(tuple, index) => match eval_in(tuple, scope) {
+			Value::Tuple(mut items) => items.swap_remove(*index),
+			v => panic!("Expected a tuple, but the value is {}", v),
+		},
+		AST::AinlC(expr, _) => Value::Inl(Box::new(eval_in(expr, scope))),
+		AST::AinrC(expr, _) => Value::Inr(Box::new(eval_in(expr, scope))),
+		AST::AcaseC(expr, left, left_body, right, right_body) => match eval_in(expr, scope) {
+			Value::Inl(v) => eval_in(left_body, &bind(scope, left, *v)),
+			Value::Inr(v) => eval_in(right_body, &bind(scope, right, *v)),
+			v => panic!("Expected a sum, but the value is {}", v),
+		},
+		AST::AemptyC(_) => Value::List(Vec::new()),
+		AST::AconsC(first, rest) => {
+			let first = eval_in(first, scope);
+			match eval_in(rest, scope) {
+				Value::List(mut items) => {
+					items.insert(0, first);
+					Value::List(items)
+				},
+				v => panic!("Expected a list, but the value is {}", v),
+			}
+		},
+		AST::AfirstC(list) | AST::ArestC(list) | AST::AisEmptyC(list) => match (ast, eval_in(list, scope)) {
+			(AST::AisEmptyC(_), Value::List(items)) => Value::Bool(items.is_empty()),
+			(_, Value::List(items)) if items.is_empty() => panic!("{} of an empty list!", ast_label(ast)),
+			(AST::AfirstC(_), Value::List(mut items)) => items.swap_remove(0),
+			(_, Value::List(mut items)) => {
+				items.remove(0);
+				Value::List(items)
+			},
+			(_, v) => panic!("Expected a list, but the value is {}", v),
+		},
+		AST::ArecordC(fields) => Value::Record(fields.iter().map(|(name, expr)| (name, eval_in(expr, scope))).collect()),
+		AST::AgetC(record, field) => match eval_in(record, scope) {
+			Value::Record(fields) => match fields.into_iter().find(|(name, _)| *name == field) {
+				Some((_, v)) => v,
+				None => panic!("Field {} not found at run time!", field),
+			},
+			v => panic!("Expected a record, but the value is {}", v),
+		},
+		AST::AconC(variant, args) => Value::Con(variant, args.iter().map(|arg| eval_in(arg, scope)).collect()),
+		AST::AmatchC(expr, arms) => match eval_in(expr, scope) {
+			Value::Con(variant, fields) => {
+				let arm = arms.iter().find(|(pattern, _, _)| pattern.as_ref().is_none_or(|pattern| pattern == variant));
+				match arm {
+					Some((_, vars, body)) => {
+						let mut arm_scope = scope.clone();
+						for (var, v) in vars.iter().zip(fields) {
+							if var != "_" {
+								arm_scope = bind(&arm_scope, var, v);
+							}
+						}
+						eval_in(body, &arm_scope)
+					},
+					None => panic!("No arm matches {}!", variant),
+				}
+			},
+			v => panic!("Expected a datatype, but the value is {}", v),
+		},
+		AST::AboxC(expr) => Value::Box(Rc::new(RefCell::new(eval_in(expr, scope)))),
+		AST::AunboxC(expr) => match eval_in(expr, scope) {
+			Value::Box(cell) => cell.borrow().clone(),
+			v => panic!("Expected a box, but the value is {}", v),
+		},
+		AST::AsetboxC(expr, value) => match eval_in(expr, scope) {
+			Value::Box(cell) => {
+				*cell.borrow_mut() = eval_in(value, scope);
+				Value::Unit
+			},
+			v => panic!("Expected a box, but the value is {}", v),
+		},
+		AST::AseqC(exprs) => {
+			let mut last = Value::Unit;
+			for expr in exprs {
+				last = eval_in(expr, scope);
+			}
+			last
+		},
+		AST::AholeC(name) => panic!("Reached the hole ?{}", name),
+		AST::None | AST::AtypeC(_) => panic!("Not an expression!"),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+
+	use super::*;
+
+    #[test]
+    fn num_test() {
+        let input = String::from("numC(5)");
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::NumT)
+    }
+
+	#[test]
+	fn plus_test() {
+        let input = String::from("plusC(numC(3), numC(5))");
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::NumT)
+    }
+
+	#[test]
+	fn mult_test() {
+        let input = String::from("multC(numC(10), numC(20))");
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::NumT)
+    }
+
+	#[test]
+	fn true_test() {
+        let input = String::from("trueC");
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::BoolT)
+    }
+
+	#[test]
+	fn false_test() {
+        let input = String::from("falseC");
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::BoolT)
+    }
+
+	#[test]
+	fn eq_test() {
+		// eqC(plusC(4, 6), multC(2, 5))
+        let input = String::from("eqC(plusC(numC(4), numC(6)), multC(numC(2), numC(5)))");
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::BoolT)
+    }
+
+	#[test]
+	fn if_test() {
+		// provided by Piazza @37
+        let input = String::from(r#"ifC(eqC(appC(fdC("n", numT, numT, plusC(numC(100), idC("n"))), numC(100)), numC(100)), appC(fdC("i", numT, boolT, eqC(idC("i"), numC(5))), numC(20)), falseC)"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::BoolT)
+    }
+
+	#[test]
+	#[should_panic]
+	fn id_test() {
+        let input = String::from(r#"idC("n")"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::BoolT)
+    }
+
+	#[test]
+	fn app_test() {
+		// provided by Piazza @37
+        let input = String::from(r#"appC(fdC("n", numT, numT, multC(numC(3), idC("n"))), numC(5))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::NumT)
+    }
+
+	#[test]
+	fn fd_test() {
+		// provided by Piazza @37
+        let input = String::from(r#"fdC("n", numT, numT, numC(52))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::FunT(Box::new(Type::NumT), Box::new(Type::NumT), Box::new(Type::TotalT)))
+    }
+
+	#[test]
+	fn rec_test() {
+		// provided by Piazza @37
+        let input = String::from(r#"recC("n", "x", numT, numT, plusC(1, 2), plusC(3, 4))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::NumT)
+    }
+
+	#[test]
+	fn rec_arg_type_test() {
+		// the parameter has the argument type (boolT), the body has the return type (numT)
+        let input = String::from(r#"recC("f", "x", boolT, numT, ifC(idC("x"), numC(1), numC(0)), appC(idC("f"), trueC))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::NumT)
+    }
+
+	#[test]
+	fn rec_call_in_if_test() {
+        let input = String::from(r#"recC("f", "x", boolT, numT, ifC(idC("x"), numC(1), plusC(numC(1), appC(idC("f"), trueC))), appC(idC("f"), falseC))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::NumT)
+    }
+
+	#[test]
+	#[should_panic]
+	fn rec_param_misuse_test() {
+		// "x" is a bool, so it cannot be added to a number
+        let input = String::from(r#"recC("f", "x", boolT, numT, plusC(idC("x"), numC(1)), appC(idC("f"), trueC))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::NumT)
+    }
+
+	#[test]
+	fn letrec_test() {
+        let input = String::from(r#"letrecC(("is-even", "n", numT, boolT, ifC(eqC(idC("n"), numC(0)), trueC, appC(idC("is-odd"), idC("n")))), ("is-odd", "n", numT, boolT, ifC(eqC(idC("n"), numC(0)), falseC, appC(idC("is-even"), idC("n")))), appC(idC("is-even"), numC(10)))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::BoolT)
+    }
+
+	#[test]
+	#[should_panic(expected = "Function return not matched in g!")]
+	fn letrec_return_test() {
+		// the body of "g" is a number but it is declared to return a bool; "f" is fine
+        let input = String::from(r#"letrecC(("f", "x", numT, boolT, appC(idC("g"), idC("x"))), ("g", "y", numT, boolT, idC("y")), appC(idC("f"), numC(1)))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::NumT)
+    }
+
+	#[test]
+	fn infer_fd_test() {
+        let input = String::from(r#"fdC("n", _, _, plusC(idC("n"), 1))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::FunT(Box::new(Type::NumT), Box::new(Type::NumT), Box::new(Type::TotalT)))
+    }
+
+	#[test]
+	fn infer_identity_test() {
+        let input = String::from(r#"fdC("x", _, _, idC("x"))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result.to_string(), "'a -> 'a")
+    }
+
+	#[test]
+	fn infer_higher_order_test() {
+        let input = String::from(r#"fdC("f", _, _, fdC("x", _, _, appC(idC("f"), idC("x"))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result.to_string(), "('a -> 'b) -> 'a -> 'b")
+    }
+
+	#[test]
+	#[should_panic(expected = "infinite type")]
+	fn infer_occurs_test() {
+        let input = String::from(r#"fdC("x", _, _, appC(idC("x"), idC("x")))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    tc(ast, &tnv);
+    }
+
+	#[test]
+	fn infer_rec_generalize_test() {
+		// "id" is used at both bool and num after its definition
+        let input = String::from(r#"recC("id", "x", _, _, idC("x"), ifC(appC(idC("id"), trueC), appC(idC("id"), numC(1)), numC(2)))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::NumT)
+    }
+
+	#[test]
+	fn let_poly_test() {
+		// the identity function is used at both numT and boolT
+        let input = String::from(r#"letC("id", fdC("x", _, _, idC("x")), ifC(appC(idC("id"), trueC), appC(idC("id"), numC(1)), numC(2)))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::NumT)
+    }
+
+	#[test]
+	fn let_scheme_test() {
+        let input = String::from(r#"letC("id", fdC("x", _, _, idC("x")), letC("n", appC(idC("id"), numC(3)), idC("id")))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let (result, schemes) = tc_schemes(ast, &tnv);
+		assert_eq!(result.to_string(), "'a -> 'a");
+		assert_eq!(schemes[0].0, "id");
+		assert_eq!(schemes[0].1.to_string(), "forall 'a. 'a -> 'a");
+		assert_eq!(schemes[1].1, Type::NumT)
+    }
+
+	#[test]
+	#[should_panic]
+	fn let_lambda_mono_test() {
+		// a lambda-bound variable is not generalized
+        let input = String::from(r#"appC(fdC("id", _, _, ifC(appC(idC("id"), trueC), appC(idC("id"), numC(1)), numC(2))), fdC("x", _, _, idC("x")))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    tc(ast, &tnv);
+    }
+
+	#[test]
+	fn ty_lam_test() {
+        let input = String::from(r#"tyLamC("a", fdC("x", a, a, idC("x")))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result.to_string(), "forall a. a -> a")
+    }
+
+	#[test]
+	fn ty_app_test() {
+        let input = String::from(r#"appC(tyAppC(tyLamC("a", fdC("x", a, a, idC("x"))), numT), numC(5))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::NumT)
+    }
+
+	#[test]
+	fn ty_app_capture_test() {
+		// instantiating "a" with "b" must rename the inner "b"
+        let input = String::from(r#"tyLamC("b", tyAppC(tyLamC("a", tyLamC("b", fdC("x", a, funT(b, a), fdC("y", b, a, idC("x"))))), b))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result.to_string(), "forall b. forall b'. b -> b' -> b");
+		let c = Box::new(Type::TvarT("c".to_string()));
+		let d = Box::new(Type::TvarT("d".to_string()));
+		let expected = Type::ForallT("c".to_string(), Box::new(Type::ForallT("d".to_string(),
+			Box::new(Type::FunT(c.clone(), Box::new(Type::FunT(d, c, Box::new(Type::TotalT))), Box::new(Type::TotalT))))));
+		assert_eq!(result, expected)
+    }
+
+	#[test]
+	fn forall_param_test() {
+        let input = String::from(r#"fdC("f", forallT("a", funT(a, a)), numT, appC(tyAppC(idC("f"), numT), numC(1)))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result.to_string(), "(forall a. a -> a) -> num")
+    }
+
+	#[test]
+	#[should_panic(expected = "Unbound type variable")]
+	fn ty_var_unbound_test() {
+        let input = String::from(r#"fdC("x", a, a, idC("x"))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    tc(ast, &tnv);
+    }
+
+	#[test]
+	#[should_panic(expected = "escapes its scope")]
+	fn ty_var_escape_test() {
+		// "y" is inferred outside of the tyLamC, so it cannot have type "a"
+        let input = String::from(r#"fdC("y", _, _, tyLamC("a", fdC("x", a, a, ifC(trueC, idC("x"), idC("y")))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    tc(ast, &tnv);
+    }
+
+	#[test]
+	fn tuple_test() {
+        let input = String::from(r#"tupleC(numC(1), trueC, fdC("x", numT, numT, idC("x")))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result.to_string(), "(num * bool * (num -> num))")
+    }
+
+	#[test]
+	fn proj_test() {
+		// a function returning two values
+        let input = String::from(r#"appC(fdC("p", tupleT(numT, boolT), boolT, projC(idC("p"), 1)), appC(fdC("n", numT, tupleT(numT, boolT), tupleC(idC("n"), eqC(idC("n"), numC(0)))), numC(3)))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::BoolT)
+    }
+
+	#[test]
+	#[should_panic(expected = "out of range")]
+	fn proj_range_test() {
+        let input = String::from(r#"projC(tupleC(numC(1), trueC), 2)"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    tc(ast, &tnv);
+    }
+
+	#[test]
+	fn sum_case_test() {
+        let input = String::from(r#"caseC(inlC(numC(3), sumT(numT, boolT)), "n", eqC(idC("n"), numC(3)), "b", idC("b"))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::BoolT)
+    }
+
+	#[test]
+	fn sum_option_test() {
+		// an option-style value: inlC carries the number, inrC means there is none
+        let input = String::from(r#"fdC("o", sumT(numT, boolT), numT, caseC(idC("o"), "n", idC("n"), "none", numC(0)))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result.to_string(), "(num + bool) -> num")
+    }
+
+	#[test]
+	#[should_panic(expected = "Case branches not matched")]
+	fn sum_branch_test() {
+        let input = String::from(r#"caseC(inrC(trueC, sumT(numT, boolT)), "n", idC("n"), "b", idC("b"))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    tc(ast, &tnv);
+    }
+
+	#[test]
+	#[should_panic(expected = "Injection not matched")]
+	fn sum_inject_test() {
+        let input = String::from(r#"inrC(numC(1), sumT(numT, boolT))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    tc(ast, &tnv);
+    }
+
+	#[test]
+	fn list_sum_test() {
+        let input = String::from(r#"recC("sum", "l", listT(numT), numT, ifC(isEmptyC(idC("l")), numC(0), plusC(firstC(idC("l")), appC(idC("sum"), restC(idC("l"))))), appC(idC("sum"), consC(numC(1), consC(numC(2), emptyC(numT)))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::NumT)
+    }
+
+	#[test]
+	fn list_length_test() {
+		// "length" is inferred as 'a list -> num and used on a bool list
+        let input = String::from(r#"recC("length", "l", _, _, ifC(isEmptyC(idC("l")), numC(0), plusC(numC(1), appC(idC("length"), restC(idC("l"))))), appC(idC("length"), consC(trueC, emptyC(_))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let (result, schemes) = tc_schemes(ast, &tnv);
+		assert_eq!(result, Type::NumT);
+		assert_eq!(schemes[0].1.to_string(), "forall 'a. 'a list -> num")
+    }
+
+	#[test]
+	#[should_panic(expected = "Invalid in cons")]
+	fn list_cons_test() {
+        let input = String::from(r#"consC(numC(1), consC(trueC, emptyC(boolT)))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    tc(ast, &tnv);
+    }
+
+	#[test]
+	fn record_get_test() {
+        let input = String::from(r#"getC(recordC(("x", numC(1)), ("y", trueC)), "y")"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::BoolT)
+    }
+
+	#[test]
+	fn record_width_test() {
+		// a function expecting {x: num} accepts {x: num, y: bool}
+        let input = String::from(r#"appC(fdC("r", recordT(("x", numT)), numT, getC(idC("r"), "x")), recordC(("x", numC(1)), ("y", trueC)))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::NumT)
+    }
+
+	#[test]
+	fn record_depth_test() {
+        let input = String::from(r#"appC(fdC("r", recordT(("p", recordT(("x", numT)))), numT, getC(getC(idC("r"), "p"), "x")), recordC(("p", recordC(("y", falseC), ("x", numC(1))))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::NumT)
+    }
+
+	#[test]
+	fn record_if_join_test() {
+        let input = String::from(r#"ifC(trueC, recordC(("x", numC(1)), ("y", trueC)), recordC(("z", falseC), ("x", numC(2))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result.to_string(), "{x: num}")
+    }
+
+	#[test]
+	#[should_panic(expected = "Function argument not matched")]
+	fn record_missing_field_test() {
+        let input = String::from(r#"appC(fdC("r", recordT(("x", numT), ("y", boolT)), numT, getC(idC("r"), "x")), recordC(("x", numC(1))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    tc(ast, &tnv);
+    }
+
+	#[test]
+	fn datatype_match_test() {
+        let input = String::from(r#"datatypeC("Shape", ("Circle", numT), ("Rect", numT, numT), fdC("s", namedT("Shape"), numT, matchC(idC("s"), ("Circle", "r", multC(numC(3), multC(idC("r"), idC("r")))), ("Rect", "w", "h", multC(idC("w"), idC("h"))))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result.to_string(), "Shape -> num")
+    }
+
+	#[test]
+	fn datatype_recursive_test() {
+		// a list of numbers declared by hand, matched with a wildcard arm
+        let input = String::from(r#"datatypeC("NumList", ("Nil"), ("Cons", numT, namedT("NumList")), matchC(conC("Cons", numC(1), conC("Nil")), ("Cons", "n", _, idC("n")), (_, numC(0))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::NumT)
+    }
+
+	#[test]
+	#[should_panic(expected = "missing: Rect")]
+	fn datatype_exhaustive_test() {
+        let input = String::from(r#"datatypeC("Shape", ("Circle", numT), ("Rect", numT, numT), matchC(conC("Circle", numC(1)), ("Circle", "r", idC("r"))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    tc(ast, &tnv);
+    }
+
+	#[test]
+	#[should_panic(expected = "Redundant pattern")]
+	fn datatype_redundant_test() {
+        let input = String::from(r#"datatypeC("Shape", ("Circle", numT), ("Rect", numT, numT), matchC(conC("Circle", numC(1)), ("Circle", "r", idC("r")), ("Rect", "w", "h", idC("w")), (_, numC(0))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    tc(ast, &tnv);
+    }
+
+	#[test]
+	#[should_panic(expected = "Match arms not matched")]
+	fn datatype_arms_test() {
+        let input = String::from(r#"datatypeC("Shape", ("Circle", numT), ("Rect", numT, numT), matchC(conC("Circle", numC(1)), ("Circle", "r", idC("r")), ("Rect", "w", "h", trueC)))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    tc(ast, &tnv);
+    }
+
+	#[test]
+	fn mu_unfold_test() {
+        let input = String::from(r#"fdC("xs", muT("l", sumT(boolT, tupleT(numT, l))), numT, caseC(unfoldC(idC("xs")), "e", numC(0), "p", projC(idC("p"), 0)))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result.to_string(), "(mu l. (bool + (num * l))) -> num")
+    }
+
+	#[test]
+	fn mu_fold_alpha_test() {
+		// the folded type is equal to the same type written with another variable
+        let input = String::from(r#"foldC(muT("l", sumT(boolT, tupleT(numT, l))), inlC(trueC, sumT(boolT, tupleT(numT, muT("k", sumT(boolT, tupleT(numT, k)))))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		let m = Type::TvarT("m".to_string());
+		let expected = Type::MuT("m".to_string(), Box::new(Type::SumT(Box::new(Type::BoolT),
+			Box::new(Type::TupleT(vec![Type::NumT, m])))));
+		assert_eq!(result, expected)
+    }
+
+	#[test]
+	fn mu_rec_sum_test() {
+        let input = String::from(r#"recC("sum", "xs", muT("l", sumT(boolT, tupleT(numT, l))), numT, caseC(unfoldC(idC("xs")), "e", numC(0), "p", plusC(projC(idC("p"), 0), appC(idC("sum"), projC(idC("p"), 1)))), appC(idC("sum"), foldC(muT("l", sumT(boolT, tupleT(numT, l))), inlC(trueC, _))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::NumT)
+    }
+
+	#[test]
+	#[should_panic(expected = "Fold not matched")]
+	fn mu_fold_mismatch_test() {
+        let input = String::from(r#"foldC(muT("l", sumT(boolT, tupleT(numT, l))), inrC(tupleC(numC(1), numC(2)), _))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    tc(ast, &tnv);
+    }
+
+	#[test]
+	fn box_test() {
+        let input = String::from(r#"letC("b", boxC(numC(1)), seqC(setboxC(idC("b"), plusC(unboxC(idC("b")), numC(1))), unboxC(idC("b"))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let (result, schemes) = tc_schemes(ast, &tnv);
+		assert_eq!(result, Type::NumT);
+		assert_eq!(schemes[0].1.to_string(), "num ref")
+    }
+
+	#[test]
+	fn setbox_unit_test() {
+        let input = String::from(r#"fdC("b", refT(boolT), unitT, setboxC(idC("b"), falseC))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result.to_string(), "bool ref -> unit")
+    }
+
+	#[test]
+	#[should_panic(expected = "setboxC value not matched")]
+	fn setbox_mismatch_test() {
+        let input = String::from(r#"setboxC(boxC(numC(1)), trueC)"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    tc(ast, &tnv);
+    }
+
+	#[test]
+	#[should_panic(expected = "Invalid control flow")]
+	fn box_value_restriction_test() {
+		// a box of an empty list is not generalized, so it cannot hold both nums and bools
+        let input = String::from(r#"letC("b", boxC(emptyC(_)), seqC(setboxC(idC("b"), consC(numC(1), emptyC(numT))), firstC(unboxC(idC("b"))), ifC(firstC(unboxC(idC("b"))), numC(1), numC(2))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    tc(ast, &tnv);
+    }
+
+	#[test]
+	fn str_literal_test() {
+		// whitespace and commas are kept inside a string literal
+        let input = String::from(r#"strAppendC('hello, ', 'big world')"#);
+		let parser: Vec<Token> = tokenize(input);
+		match &parser[2] {
+			Token::Tstr(s) => assert_eq!(s, "hello, "),
+			_ => panic!("expected a string literal"),
+		}
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::StrT)
+    }
+
+	#[test]
+	fn str_len_eq_test() {
+        let input = String::from(r#"ifC(strEqC('a', 'b'), strLenC('abc'), appC(fdC("s", strT, numT, strLenC(idC("s"))), ''))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::NumT)
+    }
+
+	#[test]
+	fn str_eq_test() {
+        let input = String::from(r#"eqC('abc', strAppendC('a', 'bc'))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::BoolT)
+    }
+
+	#[test]
+	#[should_panic(expected = "Eq not matched")]
+	fn str_eq_num_test() {
+		// "x" is a name, 'x' is a string
+        let input = String::from(r#"eqC('1', numC(1))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    tc(ast, &tnv);
+    }
+
+	#[test]
+	fn arith_prim_test() {
+        let input = String::from(r#"modC(divC(minusC(numC(10), numC(4)), numC(2)), numC(2))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::NumT)
+    }
+
+	#[test]
+	fn logic_prim_test() {
+        let input = String::from(r#"andC(ltC(numC(1), numC(2)), orC(notC(leC(idC("n"), numC(0))), falseC))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let mut tnv: HashMap<String, Type> = HashMap::new();
+	    tnv.insert("n".to_string(), Type::NumT);
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::BoolT)
+    }
+
+	#[test]
+	#[should_panic(expected = "Invalid in not")]
+	fn not_num_test() {
+        let input = String::from(r#"notC(numC(1))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    tc(ast, &tnv);
+    }
+
+	#[test]
+	#[should_panic(expected = "notC takes 1 params, found 2")]
+	fn prim_arity_test() {
+        let input = String::from(r#"notC(trueC, falseC)"#);
+		let parser: Vec<Token> = tokenize(input);
+	    parse(&parser);
+    }
+
+	#[test]
+	fn prim_signature_test() {
+		let signature = primitive("eqC").unwrap().signature();
+		assert_eq!(format!("{}", principal(&signature)), "forall 'a. 'a -> 'a -> bool");
+		assert_eq!(format!("{}", primitive("ltC").unwrap().signature()), "num -> num -> bool")
+    }
+
+	#[test]
+	#[should_panic(expected = "num -> num is not an equality type")]
+	fn eq_fun_test() {
+        let input = String::from(r#"eqC(fdC("x", numT, numT, idC("x")), fdC("y", numT, numT, numC(1)))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    tc(ast, &tnv);
+    }
+
+	#[test]
+	#[should_panic(expected = "it contains num -> bool, and functions cannot be compared")]
+	fn eq_fun_inside_test() {
+        let input = String::from(r#"eqC(tupleC(numC(1), idC("f")), tupleC(numC(2), idC("f")))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let mut tnv: HashMap<String, Type> = HashMap::new();
+	    tnv.insert("f".to_string(), Type::FunT(Box::new(Type::NumT), Box::new(Type::BoolT), Box::new(Type::TotalT)));
+	    tc(ast, &tnv);
+    }
+
+	#[test]
+	#[should_panic(expected = "not an equality type")]
+	fn eq_var_later_fun_test() {
+		// x is only known to be a function after eqC has been checked
+        let input = String::from(r#"appC(fdC("x", _, _, tupleC(eqC(idC("x"), idC("x")), appC(idC("x"), numC(1)))), fdC("y", numT, numT, idC("y")))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    tc(ast, &tnv);
+    }
+
+	#[test]
+	fn eq_first_order_test() {
+        let input = String::from(r#"eqC(consC(recordC(("x", numC(1)), ("y", 'a')), emptyC(recordT(("x", numT), ("y", strT)))), emptyC(recordT(("x", numT), ("y", strT))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::BoolT)
+    }
+
+	#[test]
+	#[should_panic(expected = "a is not an equality type, a type variable may stand for a function")]
+	fn eq_ty_var_test() {
+        let input = String::from(r#"tyLamC("a", fdC("x", a, boolT, eqC(idC("x"), idC("x"))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    tc(ast, &tnv);
+    }
+
+	#[test]
+	fn as_upcast_test() {
+        let input = String::from(r#"asC(recordC(("x", numC(1)), ("y", trueC)), recordT(("x", numT)))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(format!("{}", result), "{x: num}")
+    }
+
+	#[test]
+	fn as_instantiate_test() {
+        let input = String::from(r#"tupleC(asC(tyLamC("a", fdC("x", a, a, idC("x"))), funT(numT, numT)), letC("id", fdC("y", _, _, idC("y")), asC(idC("id"), funT(boolT, _))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(format!("{}", result), "((num -> num) * (bool -> bool))")
+    }
+
+	#[test]
+	#[should_panic(expected = "Ascription not matched! expected bool, but inferred num")]
+	fn as_mismatch_test() {
+        let input = String::from(r#"ifC(trueC, asC(plusC(numC(1), numC(2)), boolT), falseC)"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    tc(ast, &tnv);
+    }
+
+	#[test]
+	fn hole_expected_test() {
+        let input = String::from(r#"fdC("n", numT, numT, ifC(?cond, plusC(idC("n"), holeC("step")), idC("n")))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let mut tnv: HashMap<String, Type> = HashMap::new();
+	    tnv.insert("xs".to_string(), Type::ListT(Box::new(Type::StrT)));
+	    let checked = check(ast, &tnv);
+		assert_eq!(format!("{}", checked.result), "num -> num");
+		assert_eq!(checked.holes.len(), 2);
+		assert_eq!(checked.holes[0].name, "cond");
+		assert_eq!(checked.holes[0].expected, Type::BoolT);
+		assert_eq!(checked.holes[1].expected, Type::NumT);
+		assert_eq!(checked.holes[1].scope, vec![("n".to_string(), Type::NumT), ("xs".to_string(), Type::ListT(Box::new(Type::StrT)))]);
+		assert_eq!(format!("{}", checked.holes[1]), "?step : num\n    n : num\n    xs : str list")
+    }
+
+	#[test]
+	fn hole_shared_var_test() {
+		// the goal and "x" share a type variable
+        let input = String::from(r#"letC("f", fdC("x", _, _, appC(?body, idC("x"))), numC(1))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let checked = check(ast, &tnv);
+		assert_eq!(format!("{}", checked.holes[0]), "?body : 'a -> 'b\n    x : 'a");
+		assert_eq!(format!("{}", checked.schemes[0].1), "forall 'a 'b. 'a -> 'b")
+    }
+
+	#[test]
+	#[should_panic(expected = "Invalid Character: '?' can only start a hole, after a")]
+	fn hole_mark_inside_name_test() {
+        let input = String::from(r#"idC(a?b)"#);
+	    tokenize(input);
+    }
+
+	#[test]
+	fn check_fd_argument_test() {
+		// "r" gets its record type from the parameter of "apply"
+        let input = String::from(r#"appC(fdC("apply", funT(recordT(("x", numT)), numT), numT, appC(idC("apply"), recordC(("x", numC(1))))), fdC("r", getC(idC("r"), "x")))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(result, Type::NumT)
+    }
+
+	#[test]
+	fn check_fd_ascribed_test() {
+        let input = String::from(r#"asC(fdC("p", ifC(getC(idC("p"), "ok"), fdC("n", plusC(idC("n"), numC(1))), fdC("n", idC("n")))), funT(recordT(("ok", boolT)), funT(numT, numT)))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(format!("{}", result), "{ok: bool} -> num -> num")
+    }
+
+	#[test]
+	#[should_panic(expected = "Ascription not matched! in the body of fdC(\"n\"), expected bool, but inferred num")]
+	fn check_fd_body_test() {
+        let input = String::from(r#"asC(fdC("n", plusC(idC("n"), numC(1))), funT(numT, boolT))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    tc(ast, &tnv);
+    }
+
+	#[test]
+	#[should_panic(expected = "expected a parameter of type {x: num}, but it is annotated {x: num, y: num}")]
+	fn check_fd_annotation_test() {
+        let input = String::from(r#"asC(fdC("r", recordT(("x", numT), ("y", numT)), _, getC(idC("r"), "y")), funT(recordT(("x", numT)), numT))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    tc(ast, &tnv);
+    }
+
+	#[test]
+	fn typed_tree_test() {
+        let input = String::from(r#"letC("id", fdC("x", _, _, idC("x")), tupleC(appC(idC("id"), numC(1)), appC(idC("id"), trueC)))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let checked = check(ast, &tnv);
+		let expected = r#"letC("id") : (num * bool)
+  fdC("x") : 'a -> 'a
+    idC("x") : 'a
+  tupleC : (num * bool)
+    appC : num
+      idC("id") : num -> num
+      numC(1) : num
+    appC : bool
+      idC("id") : bool -> bool
+      trueC : bool
+"#;
+		assert_eq!(format!("{}", checked.tree), expected)
+    }
+
+	#[test]
+	fn typed_tree_check_mode_test() {
+		// the unannotated fdC is typed from the ascription
+        let input = String::from(r#"asC(fdC("n", ifC(ltC(idC("n"), numC(0)), numC(0), idC("n"))), funT(numT, numT))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let tree = check(ast, &tnv).tree;
+		let fd = &tree.children[0];
+		assert_eq!(format!("{}", fd.ty), "num -> num");
+		assert_eq!(fd.children[0].label, "ifC");
+		assert_eq!(fd.children[0].children.len(), 3);
+		assert_eq!(fd.children[0].children[2].ty, Type::NumT)
+    }
+
+	#[test]
+	fn env_shadowing_test() {
+		// the inner "x" shadows the outer one only inside its fdC
+        let input = String::from(r#"fdC("x", numT, _, tupleC(appC(fdC("x", boolT, _, idC("x")), trueC), idC("x")))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    let result = tc(ast, &tnv);
+		assert_eq!(format!("{}", result), "num -> (bool * num)")
+    }
+
+	#[test]
+	fn env_scope_test() {
+		let mut env = Env::new();
+		env.extend("x".to_string(), Type::NumT, Binder::Initial);
+		let mark = env.len();
+		env.extend("x".to_string(), Type::BoolT, Binder::Initial);
+		env.extend("y".to_string(), Type::StrT, Binder::Initial);
+		assert_eq!(env.lookup("x"), Type::BoolT);
+		assert_eq!(env.bindings().count(), 2);
+		env.truncate(mark);
+		assert_eq!(env.lookup("x"), Type::NumT);
+		assert_eq!(env.get("y"), None)
+    }
+
+	#[test]
+	#[should_panic(expected = "Unexpected identifier: cnt. Did you mean count? The nearest binder is count from fdC(\"count\").")]
+	fn did_you_mean_test() {
+        let input = String::from(r#"fdC("count", numT, numT, plusC(idC("cnt"), numC(1)))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    tc(ast, &tnv);
+    }
+
+	#[test]
+	#[should_panic(expected = "The nearest binder is loop from recC(\"loop\", \"n\"), which is not in scope here")]
+	fn did_you_mean_out_of_scope_test() {
+        let input = String::from(r#"tupleC(recC("loop", "n", numT, numT, idC("n"), appC(idC("loop"), numC(1))), appC(idC("lop"), numC(2)))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    tc(ast, &tnv);
+    }
+
+	#[test]
+	fn edit_distance_test() {
+		assert_eq!(edit_distance("kitten", "sitting"), 3);
+		assert_eq!(edit_distance("", "abc"), 3);
+		assert_eq!(edit_distance("same", "same"), 0)
+    }
+
+	#[test]
+	fn lint_test() {
+        let input = String::from(r#"fdC("x", numT, numT, ifC(trueC, appC(fdC("x", numT, numT, numC(1)), numC(2)), ifC(eqC(idC("x"), idC("x")), numC(0), numC(1))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let ids: Vec<&str> = lint(&ast).iter().map(|warning| warning.id).collect();
+		assert_eq!(ids, vec!["constant-condition", "shadowing", "unused-param", "self-comparison"])
+    }
+
+	#[test]
+	fn lint_clean_test() {
+		// a used parameter, distinct names and "_" prefixed parameters are fine
+        let input = String::from(r#"letC("f", fdC("x", numT, numT, plusC(idC("x"), numC(1))), fdC("_y", boolT, numT, ifC(eqC(idC("f"), idC("g")), numC(1), numC(2))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+		assert_eq!(lint(&ast), vec![])
+    }
+
+	#[test]
+	fn lint_levels_test() {
+		let mut levels = LintLevels::new();
+		levels.set("shadowing", Level::Allow);
+		levels.set("unused-param", Level::Deny);
+		assert_eq!(levels.level("shadowing"), Level::Allow);
+		assert_eq!(levels.level("unused-param"), Level::Deny);
+		assert_eq!(levels.level("self-comparison"), Level::Warn)
+    }
+
+	#[test]
+	#[should_panic(expected = "Unknown lint: shadow")]
+	fn lint_unknown_test() {
+		LintLevels::new().set("shadow", Level::Deny);
+    }
+
+	#[test]
+	fn dyn_consistent_test() {
+        let input = String::from(r#"appC(fdC("x", dynT, numT, plusC(idC("x"), numC(1))), numC(2))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+		assert_eq!(tc(ast, &tnv), Type::NumT)
+    }
+
+	#[test]
+	fn dyn_cast_insertion_test() {
+        let input = String::from(r#"fdC("x", dynT, numT, plusC(idC("x"), numC(1)))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+		match check(ast, &tnv).program {
+			AST::AfdC(_, _, _, _, body) => match *body {
+				AST::AprimC(_, args) => assert_eq!(args[0], AST::AcastC(Box::new(AST::AidC("x".to_string())), Type::DynT, Type::NumT, "x bound by fdC(\"x\")".to_string(), "plusC".to_string())),
+				other => panic!("unexpected body {:?}", other),
+			},
+			other => panic!("unexpected program {:?}", other),
+		}
+    }
+
+	#[test]
+	fn static_program_has_no_casts_test() {
+        let input = String::from(r#"letC("id", fdC("x", _, _, idC("x")), appC(idC("id"), numC(1)))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+		let checked = check(ast.clone(), &tnv);
+		assert_eq!(checked.program, ast);
+		assert_eq!(eval(&checked.program).to_string(), "1")
+    }
+
+	#[test]
+	fn dyn_eval_test() {
+        let input = String::from(r#"appC(fdC("f", dynT, numT, appC(idC("f"), numC(41))), fdC("y", numT, numT, plusC(idC("y"), numC(1))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+		assert_eq!(eval(&check(ast, &tnv).program).to_string(), "42")
     }
-} /* [END] is_key_word function */
 
-/* ====================================================================================== */
-/* ====================================================================================== */
-/* ====================================================================================== */
-/* ====================================================================================== */
-/* ====================================================================================== */
+	#[test]
+	#[should_panic(expected = "Blame x bound by fdC(\"x\"): expected num, but the value is true")]
+	fn dyn_blame_test() {
+        let input = String::from(r#"appC(fdC("x", dynT, numT, plusC(idC("x"), numC(1))), trueC)"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+		eval(&check(ast, &tnv).program);
+    }
 
-/* --------------- Type Checkier Function ---------------
- * @parameter: an AST and a HashMap (type environment)
- * @return: a Type
- * Description: the function checks for the type.
- */
-pub fn tc(ast: AST, tnv: &HashMap<String, Type>) -> Type {
-	match ast {
-		AST::AnumC(_i) => {Type::NumT},
-		AST::AplusC(op1, op2) => {
-			if tc(*op1, tnv) == Type::NumT && tc(*op2, tnv) == Type::NumT {
-				Type::NumT
-			} else {
-				panic!("Invalid in plus!")
-			}
-		},
-		AST::AmultC(op1, op2) => {
-			if tc(*op1, &tnv) == Type::NumT && tc(*op2, &tnv) == Type::NumT {
-				Type::NumT
-			} else {
-				panic!("Invalid in mult!")
-			}
-		},
-		AST::AtrueC(_b) => Type::BoolT,
-		AST::AfalseC(_b) => Type::BoolT,
-		AST::AeqC(left, right) => {
-			let left_type: Type = tc(*left, tnv);
-			let right_type: Type = tc(*right, tnv);
-			if left_type == right_type {
-				Type::BoolT
-			} else {
-				panic!("Eq not matched!")
-			}
-		},
-		AST::AifC(ifc, thenc, elsec) => {
-			let if_type = tc(*ifc, tnv);
-			if if_type != Type::BoolT {
-				panic!("Invalid control flow!")
-			} else {
-				let then_type = tc(*thenc, tnv);
-				let else_type = tc(*elsec, tnv);
-				if then_type == else_type {
-					then_type
-				} else {
-					panic!("If-else not matched!")
-				}
-			}
-		},
-		AST::AidC(s) => {
-			ty_lookup(s, tnv)
-		},
-		AST::AfdC(name, inpt, ret, body) => {
-			// println!("Recognized fun");
-			let mut etnv: HashMap<String, Type> = tnv.clone();
-			let einpt = inpt.clone();
-			etnv.insert(name.to_string(), *einpt);
-			// println!("Inserted var to einpt");
-			check_tnv(&etnv);
-			let body_type = tc(*body, &etnv);
-			if body_type == *ret {
-				Type::FunT(inpt, ret)
-			} else {
-				panic!("Function return not matched!")
-			}
-		},
-		AST::ArecC(name1, name2, type1, type2, body1, body2) => {
-			let mut etnv: HashMap<String, Type> = tnv.clone();
-			let e_type1  = type1.clone();
-			let e_type2 = type2.clone();
-			let e_type22 = type2.clone();
-			etnv.insert(name1.to_string(), Type::FunT(Box::new(*e_type1), Box::new(*e_type2)));
-			check_tnv(&etnv);
-			let mut etnv2: HashMap<String, Type> = etnv.clone();
-			etnv2.insert(name2.to_string(), *e_type22);
-			let body_type = tc(*body1, &etnv2);
-			if body_type == *type2 {
-				tc(*body2, &etnv)
-			} else {
-				panic!("Function return not matched!")
-			}
-		},
-		AST::AappC(fun, arg) => {
-			let arg_type = tc(*arg, tnv);
-			let fun_type = tc(*fun, tnv);
-			match fun_type {
-				Type::FunT(inpt_type, ret_type) => {
-					if *inpt_type == arg_type {
-						*ret_type
-					} else {
-						panic!("Function argument not matched!")
-					}
-				}
-				Type::NumT => panic!("Not a function!"),
-				Type::BoolT => panic!("Not a function!"),
-			}
-		},
-		/* these cases will not happen */
-        AST::None => Type::BoolT,
-		AST::AtypeC(_t) => Type::BoolT,
-	}
-}
+	#[test]
+	#[should_panic(expected = "Blame f bound by fdC(\"f\"): expected bool, but the value is 1")]
+	fn dyn_function_blame_test() {
+		// fdC("y") is fine; f, which only knows it as dynT, passes it a number
+        let input = String::from(r#"appC(fdC("f", dynT, dynT, appC(idC("f"), numC(1))), fdC("y", boolT, boolT, notC(idC("y"))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+		eval(&check(ast, &tnv).program);
+    }
 
-pub fn ty_lookup (id: String, tnv: &HashMap<String, Type>) -> Type {
-	match tnv.get(&id) {
-		Some(t) => t.clone(),
-		_ => panic!("Unexpected identifier: {}", &id),
-	}
-}
+	#[test]
+	fn rec_may_diverge_test() {
+		// a recC function may diverge, and so may a function calling it
+        let input = String::from(r#"recC("loop", "n", numT, numT, appC(idC("loop"), idC("n")), tupleC(idC("loop"), fdC("x", numT, numT, appC(idC("loop"), idC("x"))), fdC("y", numT, numT, idC("y"))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+		assert_eq!(tc(ast, &tnv).to_string(), "((num ~> num) * (num ~> num) * (num -> num))")
+    }
 
-pub fn check_tnv(tnv: &HashMap<String, Type>) {
-	// println!("check tnv");
-    let mut id = String::new();
-	for (var, _ty) in tnv.iter() {
-    	// println!("found {}", var);
-        id = var.to_string();
-	}
-	ty_lookup(id.to_string(), &tnv);
-}
+	#[test]
+	fn effect_polymorphic_test() {
+		// "apply" diverges exactly when the function it is given does
+        let input = String::from(r#"recC("loop", "n", numT, numT, appC(idC("loop"), idC("n")), letC("apply", fdC("f", _, _, fdC("x", _, _, appC(idC("f"), idC("x")))), tupleC(appC(idC("apply"), idC("loop")), appC(idC("apply"), fdC("y", numT, numT, idC("y"))))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+		let (result, schemes) = tc_schemes(ast, &tnv);
+		assert_eq!(result.to_string(), "((num ~> num) * (num -> num))");
+		assert_eq!(schemes[1].1.to_string(), "forall 'a 'b. ('a -> 'b) -> 'a -> 'b")
+    }
 
-#[cfg(test)]
-mod tests {
+	#[test]
+	fn declared_total_test() {
+        let input = String::from(r#"fdC("x", numT, numT, totalT, plusC(idC("x"), numC(1)))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+		assert_eq!(tc(ast, &tnv).to_string(), "num -> num")
+    }
 
-	use super::*;
+	#[test]
+	#[should_panic(expected = "fdC(\"x\") is declared total, but it calls loop, which may diverge")]
+	fn declared_total_calls_partial_test() {
+        let input = String::from(r#"recC("loop", "n", numT, numT, appC(idC("loop"), idC("n")), fdC("x", numT, numT, totalT, appC(idC("loop"), idC("x"))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    tc(ast, &tnv);
+    }
 
-    #[test]
-    fn num_test() {
-        let input = String::from("numC(5)");
+	#[test]
+	#[should_panic(expected = "a function that may diverge is used where a total one is expected")]
+	fn declared_total_param_test() {
+		// calling "f" in a total function requires "f" to be total
+        let input = String::from(r#"recC("loop", "n", numT, numT, appC(idC("loop"), idC("n")), appC(fdC("f", funT(numT, numT), numT, totalT, appC(idC("f"), numC(1))), idC("loop")))"#);
 		let parser: Vec<Token> = tokenize(input);
 	    let ast = parse(&parser);
 	    let tnv: HashMap<String, Type> = HashMap::new();
-	    let result = tc(ast, &tnv);
-		assert_eq!(result, Type::NumT)
+	    tc(ast, &tnv);
     }
 
 	#[test]
-	fn plus_test() {
-        let input = String::from("plusC(numC(3), numC(5))");
+	fn countdown_total_test() {
+		// n - 1 under a guard that n is positive, so "down" is total and can be called from a total function
+        let input = String::from(r#"recC("down", "n", numT, numT, ifC(ltC(numC(0), idC("n")), appC(idC("down"), minusC(idC("n"), numC(1))), numC(0)), fdC("x", numT, numT, totalT, appC(idC("down"), idC("x"))))"#);
 		let parser: Vec<Token> = tokenize(input);
 	    let ast = parse(&parser);
+		assert_eq!(lint(&ast), vec![]);
 	    let tnv: HashMap<String, Type> = HashMap::new();
-	    let result = tc(ast, &tnv);
-		assert_eq!(result, Type::NumT)
+	    let (result, schemes) = tc_schemes(ast, &tnv);
+		assert_eq!(result.to_string(), "num -> num");
+		assert_eq!(schemes[0].1.to_string(), "num -> num")
     }
 
 	#[test]
-	fn mult_test() {
-        let input = String::from("multC(numC(10), numC(20))");
+	fn else_guard_test() {
+		// the else branch of leC(n, 0) knows that n is at least 1
+        let input = String::from(r#"recC("sum", "n", numT, numT, ifC(leC(idC("n"), numC(0)), numC(0), plusC(idC("n"), appC(idC("sum"), minusC(idC("n"), numC(1))))), idC("sum"))"#);
 		let parser: Vec<Token> = tokenize(input);
 	    let ast = parse(&parser);
+		assert_eq!(termination("sum", "n", match &ast { AST::ArecC(_, _, _, _, body, _) => body, _ => unreachable!() }), Ok(()));
 	    let tnv: HashMap<String, Type> = HashMap::new();
-	    let result = tc(ast, &tnv);
-		assert_eq!(result, Type::NumT)
+		assert_eq!(tc(ast, &tnv).to_string(), "num -> num")
     }
 
 	#[test]
-	fn true_test() {
-        let input = String::from("trueC");
+	fn unproven_termination_test() {
+        let input = String::from(r#"tupleC(recC("loop", "n", numT, numT, appC(idC("loop"), idC("n")), idC("loop")), recC("down", "n", numT, numT, appC(idC("down"), minusC(idC("n"), numC(1))), idC("down")), recC("f", "n", numT, numT, appC(idC("f"), idC("n")), idC("f")))"#);
 		let parser: Vec<Token> = tokenize(input);
 	    let ast = parse(&parser);
+		let messages: Vec<String> = lint(&ast).into_iter().filter(|warning| warning.id == "unproven-termination").map(|warning| warning.message).collect();
+		assert_eq!(messages, vec![
+			"recC(\"loop\", \"n\") may not terminate: loop is called on n, which is not smaller than n".to_string(),
+			"recC(\"down\", \"n\") may not terminate: down is called on n - 1 without a guard that n is positive".to_string(),
+			"recC(\"f\", \"n\") may not terminate: f is called on n, which is not smaller than n".to_string(),
+		]);
 	    let tnv: HashMap<String, Type> = HashMap::new();
-	    let result = tc(ast, &tnv);
-		assert_eq!(result, Type::BoolT)
+		assert_eq!(tc(ast, &tnv).to_string(), "((num ~> num) * (num ~> num) * (num ~> num))")
     }
 
 	#[test]
-	fn false_test() {
-        let input = String::from("falseC");
+	fn recursion_escapes_test() {
+		// "g" passes itself on, so its calls cannot be followed; the inner "n" shadows the parameter
+        let input = String::from(r#"tupleC(recC("g", "n", numT, numT, appC(fdC("h", funT(numT, numT), numT, appC(idC("h"), idC("n"))), idC("g")), idC("g")), recC("k", "l", listT(numT), numT, appC(fdC("l", listT(numT), numT, appC(idC("k"), restC(idC("l")))), idC("l")), idC("k")))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+		let messages: Vec<String> = lint(&ast).into_iter().filter(|warning| warning.id == "unproven-termination").map(|warning| warning.message).collect();
+		assert_eq!(messages, vec![
+			"recC(\"g\", \"n\") may not terminate: g is used as a value, so its calls cannot be followed".to_string(),
+			"recC(\"k\", \"l\") may not terminate: k is called where its parameter is shadowed".to_string(),
+		])
+    }
+
+	#[test]
+	fn check_keeps_forall_test() {
+		// the parameter of "f" is an unknown type, so the argument stays polymorphic
+        let input = String::from(r#"letC("f", fdC("g", _, _, idC("g")), appC(idC("f"), tyLamC("a", fdC("x", a, a, idC("x")))))"#);
 		let parser: Vec<Token> = tokenize(input);
 	    let ast = parse(&parser);
 	    let tnv: HashMap<String, Type> = HashMap::new();
-	    let result = tc(ast, &tnv);
-		assert_eq!(result, Type::BoolT)
+		assert_eq!(tc(ast, &tnv).to_string(), "forall a. a -> a")
     }
 
 	#[test]
-	fn eq_test() {
-		// eqC(plusC(4, 6), multC(2, 5))
-        let input = String::from("eqC(plusC(numC(4), numC(6)), multC(numC(2), numC(5)))");
+	#[should_panic(expected = "Casts were recorded for another program than the one elaborated!")]
+	fn elaborate_other_program_test() {
+        let input = String::from(r#"fdC("x", dynT, numT, plusC(idC("x"), numC(1)))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+		let copy = ast.clone();
+		let mut infer = Infer::new();
+		infer.infer(&copy, &mut Env::new());
+		infer.elaborate(&ast);
+    }
+
+	#[test]
+	#[should_panic(expected = "Blame d bound by fdC(\"d\"): expected num, but the value is true")]
+	fn dyn_case_blame_test() {
+		// the branches only have to be consistent, so the dynT one is cast to num
+        let input = String::from(r#"appC(fdC("d", dynT, numT, plusC(caseC(inrC(numC(0), sumT(numT, numT)), "a", numC(1), "b", idC("d")), numC(1))), trueC)"#);
 		let parser: Vec<Token> = tokenize(input);
 	    let ast = parse(&parser);
 	    let tnv: HashMap<String, Type> = HashMap::new();
-	    let result = tc(ast, &tnv);
-		assert_eq!(result, Type::BoolT)
+		eval(&check(ast, &tnv).program);
     }
 
 	#[test]
-	fn if_test() {
-		// provided by Piazza @37
-        let input = String::from(r#"ifC(eqC(appC(fdC("n", numT, numT, plusC(numC(100), idC("n"))), numC(100)), numC(100)), appC(fdC("i", numT, boolT, eqC(idC("i"), numC(5))), numC(20)), falseC)"#);
+	#[should_panic(expected = "Blame d bound by fdC(\"d\"): expected (dyn + dyn), but the value is true")]
+	fn dyn_case_scrutinee_blame_test() {
+        let input = String::from(r#"appC(fdC("d", dynT, numT, caseC(idC("d"), "a", numC(1), "b", numC(2))), trueC)"#);
 		let parser: Vec<Token> = tokenize(input);
 	    let ast = parse(&parser);
 	    let tnv: HashMap<String, Type> = HashMap::new();
-	    let result = tc(ast, &tnv);
-		assert_eq!(result, Type::BoolT)
+		eval(&check(ast, &tnv).program);
     }
 
 	#[test]
-	#[should_panic]
-	fn id_test() {
-        let input = String::from(r#"idC("n")"#);
+	#[should_panic(expected = "Blame d bound by fdC(\"d\"): expected num, but the value is true")]
+	fn dyn_setbox_blame_test() {
+		// the box holds numbers, so the dynT value is cast before it is stored
+        let input = String::from(r#"appC(fdC("d", dynT, numT, letC("b", boxC(numC(1)), seqC(setboxC(idC("b"), idC("d")), plusC(unboxC(idC("b")), numC(1))))), trueC)"#);
 		let parser: Vec<Token> = tokenize(input);
 	    let ast = parse(&parser);
 	    let tnv: HashMap<String, Type> = HashMap::new();
-	    let result = tc(ast, &tnv);
-		assert_eq!(result, Type::BoolT)
+		eval(&check(ast, &tnv).program);
     }
 
 	#[test]
-	fn app_test() {
-		// provided by Piazza @37
-        let input = String::from(r#"appC(fdC("n", numT, numT, multC(numC(3), idC("n"))), numC(5))"#);
+	#[should_panic(expected = "Blame d bound by fdC(\"d\"): expected num, but the value is true")]
+	fn dyn_rec_body_blame_test() {
+        let input = String::from(r#"appC(fdC("d", dynT, numT, recC("f", "n", numT, numT, idC("d"), plusC(appC(idC("f"), numC(1)), numC(1)))), trueC)"#);
 		let parser: Vec<Token> = tokenize(input);
 	    let ast = parse(&parser);
 	    let tnv: HashMap<String, Type> = HashMap::new();
-	    let result = tc(ast, &tnv);
-		assert_eq!(result, Type::NumT)
+		eval(&check(ast, &tnv).program);
     }
 
 	#[test]
-	fn fd_test() {
-		// provided by Piazza @37
-        let input = String::from(r#"fdC("n", numT, numT, numC(52))"#);
+	#[should_panic(expected = "Blame d bound by fdC(\"d\"): expected dyn list, but the value is true")]
+	fn dyn_list_blame_test() {
+        let input = String::from(r#"appC(fdC("d", dynT, numT, plusC(firstC(idC("d")), numC(1))), trueC)"#);
 		let parser: Vec<Token> = tokenize(input);
 	    let ast = parse(&parser);
 	    let tnv: HashMap<String, Type> = HashMap::new();
-	    let result = tc(ast, &tnv);
-		assert_eq!(result, Type::FunT(Box::new(Type::NumT), Box::new(Type::NumT)))
+		eval(&check(ast, &tnv).program);
     }
 
 	#[test]
-	fn rec_test() {
-		// provided by Piazza @37
-        let input = String::from(r#"recC("n", "x", numT, numT, plusC(1, 2), plusC(3, 4))"#);
+	#[should_panic(expected = "Blame d bound by fdC(\"d\"): expected num list, but the value is true")]
+	fn dyn_cons_blame_test() {
+        let input = String::from(r#"appC(fdC("d", dynT, numT, firstC(consC(numC(1), idC("d")))), trueC)"#);
 		let parser: Vec<Token> = tokenize(input);
 	    let ast = parse(&parser);
 	    let tnv: HashMap<String, Type> = HashMap::new();
-	    let result = tc(ast, &tnv);
-		assert_eq!(result, Type::NumT)
+		eval(&check(ast, &tnv).program);
+    }
+
+	#[test]
+	#[should_panic(expected = "fdC(\"z\") is declared total, but it calls unboxC, which may diverge")]
+	fn box_knot_divergence_test() {
+		// Landin's knot: the boxed function is replaced by one calling through the box
+        let input = String::from(r#"letC("b", boxC(fdC("x", numT, numT, idC("x"))), seqC(setboxC(idC("b"), fdC("y", numT, numT, appC(unboxC(idC("b")), idC("y")))), appC(fdC("z", numT, numT, totalT, appC(unboxC(idC("b")), idC("z"))), numC(1))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+		tc(ast, &tnv);
+    }
+
+	#[test]
+	#[should_panic(expected = "fdC(\"w\") is declared total, but it calls fdC(\"x\"), which may diverge")]
+	fn fold_self_application_divergence_test() {
+		// a function unfolded from its own fold can loop without recC
+        let input = String::from(r#"fdC("w", numT, numT, totalT, appC(fdC("x", muT("a", funT(a, numT)), numT, appC(unfoldC(idC("x")), idC("x"))), foldC(muT("a", funT(a, numT)), fdC("x", muT("a", funT(a, numT)), numT, appC(unfoldC(idC("x")), idC("x"))))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+		tc(ast, &tnv);
+    }
+
+	#[test]
+	fn latent_effect_bound_test() {
+		// a total f and a diverging g: the result may diverge, and needs no f that does
+        let input = String::from(r#"recC("loop", "n", numT, numT, appC(idC("loop"), idC("n")), letC("both", fdC("f", funT(numT, numT), _, fdC("g", funT(numT, numT), _, fdC("x", numT, numT, plusC(appC(idC("f"), idC("x")), appC(idC("g"), idC("x")))))), tupleC(appC(appC(idC("both"), fdC("y", numT, numT, idC("y"))), idC("loop")), appC(appC(idC("both"), fdC("y", numT, numT, idC("y"))), fdC("y", numT, numT, idC("y"))))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+		assert_eq!(tc(ast, &tnv).to_string(), "((num ~> num) * (num -> num))");
+    }
+
+	#[test]
+	#[should_panic(expected = "fdC(\"z\") is declared total, but it calls appC, which may diverge")]
+	fn latent_effect_bound_total_test() {
+		// the same function, given loop, may diverge
+        let input = String::from(r#"recC("loop", "n", numT, numT, appC(idC("loop"), idC("n")), letC("both", fdC("f", funT(numT, numT), _, fdC("g", funT(numT, numT), _, fdC("x", numT, numT, plusC(appC(idC("f"), idC("x")), appC(idC("g"), idC("x")))))), fdC("z", numT, numT, totalT, appC(appC(appC(idC("both"), fdC("y", numT, numT, idC("y"))), idC("loop")), idC("z")))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+		tc(ast, &tnv);
+    }
+
+	#[test]
+	#[should_panic(expected = "a function that may diverge is used where a total one is expected")]
+	fn declared_div_arg_test() {
+		// "y" is declared divT, so it cannot be passed where a total function is needed
+        let input = String::from(r#"appC(fdC("f", funT(numT, numT), numT, totalT, appC(idC("f"), numC(1))), fdC("y", numT, numT, divT, idC("y")))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+	    tc(ast, &tnv);
+    }
+
+	#[test]
+	fn declared_div_test() {
+        let input = String::from(r#"fdC("y", numT, numT, divT, idC("y"))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+		assert_eq!(tc(ast, &tnv).to_string(), "num ~> num");
+    }
+
+	#[test]
+	fn record_join_rollback_test() {
+		// field "a" is dropped from the join, so x must not stay bound to bool
+        let input = String::from(r#"fdC("x", _, numT, letC("r", ifC(trueC, recordC(("a", tupleC(idC("x"), numC(1)))), recordC(("a", tupleC(trueC, 's')))), plusC(idC("x"), numC(1))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+		assert_eq!(tc(ast, &tnv).to_string(), "num -> num");
+    }
+
+	#[test]
+	#[should_panic(expected = "Blame f bound by fdC(\"f\"): expected num, but the value is true")]
+	fn dyn_checked_fd_blame_test() {
+		// fdC("y") is checked against funT(dynT, numT); f passes it a bool
+        let input = String::from(r#"appC(fdC("f", funT(dynT, numT), numT, appC(idC("f"), trueC)), fdC("y", numT, numT, plusC(idC("y"), numC(1))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+		eval(&check(ast, &tnv).program);
+    }
+
+	#[test]
+	fn dyn_get_test() {
+        let input = String::from(r#"appC(fdC("d", dynT, numT, plusC(getC(idC("d"), "x"), numC(1))), recordC(("x", numC(1)), ("y", trueC)))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+		assert_eq!(eval(&check(ast, &tnv).program).to_string(), "2")
+    }
+
+	#[test]
+	#[should_panic(expected = "Blame d bound by fdC(\"d\"): expected {x: dyn}, but the value is {y: 1}")]
+	fn dyn_get_blame_test() {
+        let input = String::from(r#"appC(fdC("d", dynT, numT, plusC(getC(idC("d"), "x"), numC(1))), recordC(("y", numC(1))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+		eval(&check(ast, &tnv).program);
+    }
+
+	#[test]
+	#[should_panic(expected = "projC needs a tuple whose size is known, not dyn!")]
+	fn dyn_proj_test() {
+        let input = String::from(r#"fdC("d", dynT, numT, projC(idC("d"), 0))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+		tc(ast, &tnv);
+    }
+
+	#[test]
+	#[should_panic(expected = "Blame d bound by fdC(\"d\"): expected Shape, but the value is 1")]
+	fn dyn_match_blame_test() {
+		// the arms tell which datatype the dynT must be
+        let input = String::from(r#"datatypeC("Shape", ("Circle", numT), appC(fdC("d", dynT, numT, matchC(idC("d"), ("Circle", "r", idC("r")))), numC(1)))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+		eval(&check(ast, &tnv).program);
+    }
+
+	#[test]
+	fn dyn_unfold_test() {
+        let input = String::from(r#"appC(fdC("d", dynT, numT, plusC(unfoldC(idC("d")), numC(1))), numC(2))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+		assert_eq!(eval(&check(ast, &tnv).program).to_string(), "3")
+    }
+
+	#[test]
+	fn dyn_ty_app_test() {
+        let input = String::from(r#"appC(fdC("d", dynT, numT, tyAppC(idC("d"), numT)), numC(4))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+		assert_eq!(eval(&check(ast, &tnv).program).to_string(), "4")
+    }
+
+	#[test]
+	#[should_panic(expected = "dyn is not an equality type, dyn may stand for a function")]
+	fn dyn_eq_test() {
+        let input = String::from(r#"eqC(asC(fdC("x", numT, numT, idC("x")), dynT), asC(numC(1), dynT))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+		tc(ast, &tnv);
+    }
+
+	#[test]
+	#[should_panic(expected = "a function that may diverge is used where a total one is expected")]
+	fn dyn_total_ascription_test() {
+		// loop leaves its effect behind in dynT, so it cannot come back out as total
+        let input = String::from(r#"recC("loop", "n", numT, numT, appC(idC("loop"), idC("n")), fdC("z", numT, numT, totalT, appC(asC(asC(idC("loop"), dynT), funT(numT, numT, totalT)), idC("z"))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+		tc(ast, &tnv);
+    }
+
+	#[test]
+	#[should_panic(expected = "a function that may diverge is used where a total one is expected")]
+	fn dyn_total_param_test() {
+        let input = String::from(r#"recC("loop", "n", numT, numT, appC(idC("loop"), idC("n")), appC(fdC("f", funT(numT, numT), numT, totalT, appC(idC("f"), numC(1))), asC(idC("loop"), dynT)))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+		tc(ast, &tnv);
+    }
+
+	#[test]
+	fn dyn_function_diverges_test() {
+		// a function out of dynT may diverge, whatever it was before
+        let input = String::from(r#"asC(asC(fdC("x", numT, numT, idC("x")), dynT), funT(numT, numT))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+		assert_eq!(tc(ast, &tnv).to_string(), "num ~> num");
+    }
+
+	#[test]
+	#[should_panic(expected = "fdC(\"z\") is declared total, but it calls g, which may diverge")]
+	fn effect_bound_after_join_test() {
+		// joining f with loop makes f, and so g, diverge after the bound on g was kept
+        let input = String::from(r#"recC("loop", "n", numT, numT, appC(idC("loop"), idC("n")), fdC("f", funT(numT, numT), numT, letC("g", fdC("x", numT, numT, appC(idC("f"), idC("x"))), seqC(ifC(trueC, idC("f"), idC("loop")), appC(fdC("z", numT, numT, totalT, appC(idC("g"), idC("z"))), numC(1))))))"#);
+		let parser: Vec<Token> = tokenize(input);
+	    let ast = parse(&parser);
+	    let tnv: HashMap<String, Type> = HashMap::new();
+		tc(ast, &tnv);
     }
 }
@@ -8,8 +8,22 @@ fn main() {
 
     println!("\n\n---------- tc200 Program Started ----------");
 
-    // get file from command line argument (will only accept one argument)
-    let file_name = args().nth(1).unwrap().to_string();
+    // get file from command line argument; --show-types also prints the typed tree,
+    // --allow <lint> / --deny <lint> change the level of a lint, and --eval runs the program
+    let mut show_types = false;
+    let mut run = false;
+    let mut levels = LintLevels::new();
+    let mut file_name = String::new();
+    let mut arguments = args().skip(1);
+    while let Some(arg) = arguments.next() {
+        match arg.as_str() {
+            "--show-types" => show_types = true,
+            "--eval" => run = true,
+            "--allow" => levels.set(&arguments.next().expect("--allow needs a lint"), Level::Allow),
+            "--deny" => levels.set(&arguments.next().expect("--deny needs a lint"), Level::Deny),
+            _ => file_name = arg,
+        }
+    }
     let mut file = File::open("./src/files/".to_string() + &file_name).expect("Unable to open the file");
     let mut content = String::new();
     file.read_to_string(&mut content).expect("Unable to read the file");
@@ -18,8 +32,37 @@ fn main() {
 
 	let parser: Vec<Token> = tokenize(content);
     let ast = parse(&parser);
+
+    let mut denied = 0;
+    for warning in lint(&ast) {
+        match levels.level(warning.id) {
+            Level::Allow => {},
+            Level::Warn => println!("warning[{}]: {}", warning.id, warning.message),
+            Level::Deny => {
+                println!("error[{}]: {}", warning.id, warning.message);
+                denied += 1;
+            },
+        }
+    }
+    if denied > 0 {
+        println!("Stopped by {} denied lint(s).", denied);
+        std::process::exit(1);
+    }
+
     let tnv: HashMap<String, Type> = HashMap::new();
-    let result = tc(ast, &tnv);
+    let checked = check(ast, &tnv);
 
-    println!("The type is {{ {:?} }}.", result);
+    for (name, scheme) in checked.schemes.iter() {
+        println!("{} : {}", name, scheme);
+    }
+    for hole in checked.holes.iter() {
+        println!("Hole {}", hole);
+    }
+    if show_types {
+        print!("{}", checked.tree);
+    }
+    println!("The type is {{ {} }}.", checked.result);
+    if run {
+        println!("The value is {}.", eval(&checked.program));
+    }
 }